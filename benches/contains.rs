@@ -0,0 +1,55 @@
+//! Benchmarks for checking whether versions are contained in affected ranges
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use rustsec::{
+    osv::{affected_ranges, find_containing},
+    Version, VersionReq,
+};
+
+/// Generate versions spread across the ones covered by the ranges below
+fn synthetic_versions(count: u64) -> Vec<Version> {
+    (0..count)
+        .map(|i| {
+            let mut version = Version::new(i % 5, i % 11, i % 3);
+            if i % 4 == 0 {
+                version = Version::parse(&format!("{}-rc.{}", version, i % 2)).unwrap();
+            }
+            version
+        })
+        .collect()
+}
+
+fn bench_contains(c: &mut Criterion) {
+    let unaffected: Vec<_> = [
+        "^0.3.2",
+        ">= 1.4.0, < 2.0.0",
+        "> 2.7.1, <= 3.9.0",
+        ">= 4.2.0",
+    ]
+    .iter()
+    .map(|req| VersionReq::parse(req).unwrap())
+    .collect();
+    let ranges = affected_ranges(&unaffected).unwrap();
+    let versions = synthetic_versions(10_000);
+
+    c.bench_function("contains (10000 versions)", |b| {
+        b.iter(|| {
+            versions
+                .iter()
+                .filter(|v| ranges.iter().any(|range| range.contains(black_box(v))))
+                .count()
+        })
+    });
+
+    c.bench_function("find_containing (10000 versions)", |b| {
+        b.iter(|| {
+            versions
+                .iter()
+                .filter(|v| find_containing(&ranges, black_box(v)).is_some())
+                .count()
+        })
+    });
+}
+
+criterion_group!(benches, bench_contains);
+criterion_main!(benches);
@@ -0,0 +1,34 @@
+//! Benchmarks for computing affected ranges from large lists of unaffected
+//! version requirements
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use rustsec::{osv::affected_ranges, VersionReq};
+
+/// Generate a list of synthetic, partly overlapping unaffected requirements
+fn synthetic_reqs(count: u64) -> Vec<VersionReq> {
+    (0..count)
+        .map(|i| {
+            let req = match i % 4 {
+                0 => format!(">= {}.{}.0, < {}.0.0", i / 10, i % 10, i / 10 + 1),
+                1 => format!("^{}.{}.{}", i / 100, i % 100, i % 7),
+                2 => format!("~{}.{}", i / 10, i % 10),
+                _ => format!("= {}.{}.{}", i / 100, i % 10, i % 13),
+            };
+
+            VersionReq::parse(&req).unwrap()
+        })
+        .collect()
+}
+
+fn bench_affected_ranges(c: &mut Criterion) {
+    for &count in &[1_000, 5_000] {
+        let reqs = synthetic_reqs(count);
+
+        c.bench_function(&format!("affected_ranges ({} requirements)", count), |b| {
+            b.iter(|| affected_ranges(black_box(&reqs)).unwrap())
+        });
+    }
+}
+
+criterion_group!(benches, bench_affected_ranges);
+criterion_main!(benches);
@@ -12,8 +12,15 @@ pub mod parser;
 pub mod versions;
 
 pub use self::{
-    affected::Affected, category::Category, date::Date, id::Id, informational::Informational,
-    keyword::Keyword, linter::Linter, metadata::Metadata, versions::Versions,
+    affected::Affected,
+    category::Category,
+    date::Date,
+    id::Id,
+    informational::Informational,
+    keyword::Keyword,
+    linter::Linter,
+    metadata::Metadata,
+    versions::{CachedVersions, Versions},
 };
 pub use cvss::Severity;
 
@@ -4,6 +4,8 @@
 //! and `unaffected_versions` sections of the `[advisory]`, but can't be
 //! used
 
+use crate::osv::{all_unaffected_ranges, RangeError, UnaffectedRange};
+use once_cell::sync::OnceCell;
 use semver::{Version, VersionReq};
 use serde::{Deserialize, Serialize};
 
@@ -21,6 +23,14 @@ pub struct Versions {
 
 impl Versions {
     /// Is the given version of a package vulnerable?
+    ///
+    /// Versions are matched against the requirements the same way Cargo
+    /// matches them, using `VersionReq::matches` (see
+    /// [`cargo_matches`](crate::osv::cargo_matches)). This differs from the affected
+    /// versions exported to OSV (see [`crate::osv::affects`]) for
+    /// pre-releases: e.g. with `patched = [">= 1.2.0"]`, `1.3.0-beta` is
+    /// vulnerable here, since Cargo never selects it for that requirement,
+    /// but isn't affected in OSV terms, as it lies above the fix.
     pub fn is_vulnerable(&self, version: &Version) -> bool {
         if self.patched.iter().any(|req| req.matches(version)) {
             return false;
@@ -32,4 +42,185 @@ impl Versions {
 
         true
     }
+
+    /// Get the ranges of versions which are not affected, parsed from both
+    /// the `patched` and `unaffected` requirements.
+    ///
+    /// The ranges are merged and tagged with their
+    /// [`RangeOrigin`](crate::osv::RangeOrigin) by [`all_unaffected_ranges`],
+    /// the same way as for the OSV export. They are parsed on every call, so
+    /// callers needing them repeatedly should use [`CachedVersions`].
+    pub fn unaffected_ranges(&self) -> Result<Vec<UnaffectedRange>, RangeError> {
+        all_unaffected_ranges(&self.patched, &self.unaffected)
+    }
+}
+
+/// [`Versions`] of an advisory along with their unaffected ranges, which are
+/// parsed on first access and cached afterwards.
+///
+/// The versions can't be modified through this wrapper, so the cached ranges
+/// always match them. Use [`CachedVersions::into_inner`] to modify them.
+#[derive(Clone, Debug, Default)]
+pub struct CachedVersions {
+    versions: Versions,
+    unaffected_ranges: OnceCell<Result<Vec<UnaffectedRange>, RangeError>>,
+}
+
+impl CachedVersions {
+    /// Wrap the given versions, without parsing them yet
+    pub fn new(versions: Versions) -> Self {
+        CachedVersions {
+            versions,
+            unaffected_ranges: OnceCell::new(),
+        }
+    }
+
+    /// Get the wrapped versions
+    pub fn versions(&self) -> &Versions {
+        &self.versions
+    }
+
+    /// Unwrap the versions, discarding the cached ranges
+    pub fn into_inner(self) -> Versions {
+        self.versions
+    }
+
+    /// Get the ranges of versions which are not affected, like
+    /// [`Versions::unaffected_ranges`].
+    ///
+    /// They are parsed on first access only; later calls return the cached
+    /// ranges, or the cached error if parsing failed.
+    pub fn unaffected_ranges(&self) -> Result<&[UnaffectedRange], RangeError> {
+        self.unaffected_ranges
+            .get_or_init(|| self.versions.unaffected_ranges())
+            .as_ref()
+            .map(Vec::as_slice)
+            .map_err(Clone::clone)
+    }
+}
+
+impl From<Versions> for CachedVersions {
+    fn from(versions: Versions) -> Self {
+        CachedVersions::new(versions)
+    }
+}
+
+impl PartialEq for CachedVersions {
+    fn eq(&self, other: &Self) -> bool {
+        // The cached ranges are derived from the versions
+        self.versions == other.versions
+    }
+}
+
+impl Eq for CachedVersions {}
+
+#[cfg(test)]
+mod tests {
+    use super::{CachedVersions, Versions};
+    use crate::osv::{all_unaffected_ranges, RangeOrigin};
+    use semver::{Version, VersionReq};
+
+    #[test]
+    fn unaffected_ranges() {
+        let mut versions = Versions {
+            patched: vec![VersionReq::parse(">= 1.2.0").unwrap()],
+            unaffected: vec![VersionReq::parse("< 0.5.0").unwrap()],
+        };
+
+        let ranges = versions.unaffected_ranges().unwrap();
+        assert_eq!(ranges.len(), 2);
+        assert_eq!(ranges[0].to_string(), "<0.5.0");
+        assert_eq!(ranges[0].origin(), RangeOrigin::Unaffected);
+        assert_eq!(ranges[1].to_string(), ">=1.2.0");
+        assert_eq!(ranges[1].origin(), RangeOrigin::Patched);
+        assert_eq!(
+            ranges,
+            all_unaffected_ranges(&versions.patched, &versions.unaffected).unwrap()
+        );
+
+        // Changes to the requirements are picked up
+        versions.patched.clear();
+        assert_eq!(versions.unaffected_ranges().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn cached_unaffected_ranges() {
+        let versions = Versions {
+            patched: vec![VersionReq::parse(">= 1.2.0").unwrap()],
+            unaffected: vec![VersionReq::parse("< 0.5.0").unwrap()],
+        };
+        let cached = CachedVersions::from(versions.clone());
+
+        let ranges = cached.unaffected_ranges().unwrap();
+        assert_eq!(ranges, versions.unaffected_ranges().unwrap().as_slice());
+
+        // Repeated access returns the very same parsed ranges
+        let again = cached.unaffected_ranges().unwrap();
+        assert!(std::ptr::eq(ranges, again));
+
+        assert_eq!(cached, CachedVersions::new(versions.clone()));
+        assert_eq!(cached.into_inner(), versions);
+
+        let invalid = CachedVersions::new(Versions {
+            patched: vec![VersionReq::parse(">= 1.0.0, = 1.2.0").unwrap()],
+            unaffected: vec![],
+        });
+        assert_eq!(
+            invalid.unaffected_ranges().unwrap_err(),
+            invalid.versions().unaffected_ranges().unwrap_err()
+        );
+    }
+
+    #[test]
+    fn bare_major_upper_bound() {
+        // `< 2` is never satisfied by pre-releases of `2.0.0`, so they're
+        // vulnerable along with the rest of the `2.x` line
+        let versions = Versions {
+            patched: vec![VersionReq::parse(">= 2.1.0").unwrap()],
+            unaffected: vec![VersionReq::parse("< 2").unwrap()],
+        };
+
+        assert!(!versions.is_vulnerable(&Version::parse("1.9.9").unwrap()));
+        assert!(versions.is_vulnerable(&Version::parse("2.0.0-alpha").unwrap()));
+        assert!(versions.is_vulnerable(&Version::parse("2.0.0").unwrap()));
+        assert!(!versions.is_vulnerable(&Version::parse("2.1.0").unwrap()));
+    }
+
+    #[test]
+    fn prerelease_above_fix() {
+        let versions = Versions {
+            patched: vec![VersionReq::parse(">= 1.2.0").unwrap()],
+            unaffected: vec![],
+        };
+        let beta = Version::parse("1.3.0-beta").unwrap();
+
+        // Cargo doesn't match the pre-release, while the OSV ranges place it
+        // above the fix
+        assert!(versions.is_vulnerable(&beta));
+        assert!(!crate::osv::affects(
+            &beta,
+            &versions.patched,
+            &versions.unaffected
+        ));
+        assert!(!crate::osv::is_version_affected(&beta, &versions.patched));
+
+        // Both agree on releases
+        let release = Version::new(1, 3, 0);
+        assert!(!versions.is_vulnerable(&release));
+        assert!(!crate::osv::affects(
+            &release,
+            &versions.patched,
+            &versions.unaffected
+        ));
+    }
+
+    #[test]
+    fn bare_patched_version() {
+        // Like in Cargo, a version without an operator is a caret requirement
+        let versions: Versions = toml::from_str(r#"patched = ["1.2.3"]"#).unwrap();
+
+        let ranges = versions.unaffected_ranges().unwrap();
+        assert_eq!(ranges.len(), 1);
+        assert_eq!(ranges[0].to_string(), ">=1.2.3, <2.0.0");
+    }
 }
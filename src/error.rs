@@ -152,6 +152,12 @@ impl From<semver::ReqParseError> for Error {
     }
 }
 
+impl From<crate::osv::RangeError> for Error {
+    fn from(other: crate::osv::RangeError) -> Self {
+        format_err!(ErrorKind::Version, &other)
+    }
+}
+
 impl From<toml::de::Error> for Error {
     fn from(other: toml::de::Error) -> Self {
         format_err!(ErrorKind::Parse, &other)
@@ -0,0 +1,41 @@
+//! Support for exporting advisories in the [OSV] format.
+//!
+//! With the `tracing` feature enabled, requirements converted into ranges
+//! and the resulting affected ranges are logged as `tracing` events at debug
+//! level.
+//!
+//! [OSV]: https://github.com/google/osv
+
+mod affected;
+mod error;
+mod event;
+mod matching;
+mod range;
+mod ranges;
+#[cfg(feature = "semver1")]
+mod semver1;
+mod unaffected_range;
+
+pub use self::{
+    affected::{
+        OsvAffected, OsvEcosystemSpecific, OsvRangeDatabaseSpecific, OsvRangeType, OsvTimeline,
+    },
+    error::RangeError,
+    event::{osv_introduced_floor, OsvBoundStyle, OsvEvent},
+    matching::cargo_matches,
+    range::{OsvRange, Position},
+    ranges::{
+        affected_interval_count, affected_ranges, affected_ranges_with_explicit_start,
+        affected_ranges_with_metadata, affected_ranges_within, affected_sets_disjoint, affects,
+        all_unaffected_ranges, complement, count_affected_published, earliest_affected,
+        earliest_fixed, find_conflicting_advisories, find_containing, is_affected_prerelease,
+        is_version_affected, merge_affected, osv_ranges_to_version_req, patched_from_osv_ranges,
+        recommend_upgrade, snap_to_versions, union_affected, unsupported_requirements,
+        validate_osv_ranges, validate_ranges, AffectedEvents, AffectedRanges, Conflict,
+        UnsupportedReason,
+    },
+    unaffected_range::{
+        deserialize_ranges, parse_req_structure, AuthoredRange, Bound, RangeOrigin,
+        StrictConversion, UnaffectedRange,
+    },
+};
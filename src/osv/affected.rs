@@ -0,0 +1,330 @@
+//! Affected versions of a package, as exported into an OSV `affected` entry
+
+use super::{affected_ranges, OsvBoundStyle, OsvEvent, OsvRange};
+use semver::{Version, VersionReq};
+use serde::{Deserialize, Serialize};
+
+/// Versions of a package affected by a vulnerability, in the format of the
+/// `ranges` and `ecosystem_specific` fields of an OSV `affected` entry.
+///
+/// If the advisory's requirements can't be expressed as a `SEMVER` range
+/// without losing information, no ranges are emitted and the original
+/// requirements are preserved in `ecosystem_specific` instead.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct OsvAffected {
+    /// Timelines of the affected versions
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub ranges: Vec<OsvTimeline>,
+
+    /// RustSec-specific data about the affected versions
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ecosystem_specific: Option<OsvEcosystemSpecific>,
+}
+
+impl OsvAffected {
+    /// Compute the affected versions from the requirements matching versions
+    /// which are not affected (i.e. the `patched` and `unaffected` fields of
+    /// an advisory).
+    ///
+    /// If the unaffected versions cover the entire version line, the result
+    /// has no ranges at all, rather than a range without events.
+    pub fn from_unaffected(unaffected: &[VersionReq]) -> Self {
+        Self::from_ranges(unaffected, OsvRange::to_osv_events)
+    }
+
+    /// Compute the affected versions like [`OsvAffected::from_unaffected`],
+    /// expressing the upper boundaries of ranges in the given style.
+    ///
+    /// Converting between the styles is best-effort, based on the given
+    /// sorted list of published versions; see
+    /// [`OsvRange::to_osv_events_in_style`].
+    pub fn from_unaffected_in_style(
+        unaffected: &[VersionReq],
+        style: OsvBoundStyle,
+        published: &[Version],
+    ) -> Self {
+        Self::from_ranges(unaffected, |range| {
+            range.to_osv_events_in_style(style, published)
+        })
+    }
+
+    /// Note which of the `fixed` events are for versions which were yanked.
+    ///
+    /// Users can't upgrade to a yanked version, so such fixes are listed in
+    /// the `database_specific` field of their range. The events are left
+    /// as-is, since they still describe the affected versions accurately.
+    pub fn with_yanked_fixes(mut self, yanked: &[Version]) -> Self {
+        for timeline in &mut self.ranges {
+            let yanked_fixes: Vec<_> = timeline
+                .events
+                .iter()
+                .filter_map(|event| match event {
+                    OsvEvent::Fixed(version) if yanked.contains(version) => Some(version.clone()),
+                    _ => None,
+                })
+                .collect();
+
+            if !yanked_fixes.is_empty() {
+                timeline.database_specific = Some(OsvRangeDatabaseSpecific { yanked_fixes });
+            }
+        }
+
+        self
+    }
+
+    /// Compute the affected versions, converting each range into events with
+    /// the given function
+    fn from_ranges(unaffected: &[VersionReq], events: impl Fn(&OsvRange) -> Vec<OsvEvent>) -> Self {
+        match affected_ranges(unaffected) {
+            // No versions are affected, so there's nothing to describe
+            Ok(ranges) if ranges.is_empty() => OsvAffected {
+                ranges: vec![],
+                ecosystem_specific: None,
+            },
+            Ok(ranges) => OsvAffected {
+                ranges: vec![OsvTimeline {
+                    range_type: OsvRangeType::Semver,
+                    events: ranges.iter().flat_map(events).collect(),
+                    database_specific: None,
+                }],
+                ecosystem_specific: None,
+            },
+            Err(_) => OsvAffected {
+                ranges: vec![],
+                ecosystem_specific: Some(OsvEcosystemSpecific {
+                    unaffected: unaffected.iter().map(ToString::to_string).collect(),
+                }),
+            },
+        }
+    }
+}
+
+/// A single OSV range: a timeline of events of the given type
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct OsvTimeline {
+    /// How the versions in the events are to be interpreted
+    #[serde(rename = "type")]
+    pub range_type: OsvRangeType,
+
+    /// Events in order of the versions they happened in
+    pub events: Vec<OsvEvent>,
+
+    /// RustSec-specific data about the range
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub database_specific: Option<OsvRangeDatabaseSpecific>,
+}
+
+/// Types of OSV ranges
+#[derive(Copy, Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub enum OsvRangeType {
+    /// Versions are compared according to semver precedence
+    #[serde(rename = "SEMVER")]
+    Semver,
+}
+
+/// The `ecosystem_specific` field of an OSV `affected` entry
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct OsvEcosystemSpecific {
+    /// Original requirements matching unaffected versions, preserved when
+    /// they can't be exported as `SEMVER` ranges
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub unaffected: Vec<String>,
+}
+
+/// The `database_specific` field of an OSV range
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct OsvRangeDatabaseSpecific {
+    /// Versions in `fixed` events which were yanked, so they can't be
+    /// upgraded to
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub yanked_fixes: Vec<Version>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{OsvAffected, OsvBoundStyle};
+    use semver::{Version, VersionReq};
+    use serde_json::json;
+
+    fn reqs(input: &[&str]) -> Vec<VersionReq> {
+        input
+            .iter()
+            .map(|s| VersionReq::parse(s).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn semver_ranges() {
+        let affected = OsvAffected::from_unaffected(&reqs(&[">= 1.2.0, < 2.0.0", ">= 2.1.0"]));
+
+        assert_eq!(
+            serde_json::to_value(affected).unwrap(),
+            json!({
+                "ranges": [{
+                    "type": "SEMVER",
+                    "events": [
+                        {"introduced": "0.0.0"},
+                        {"fixed": "1.2.0"},
+                        {"introduced": "2.0.0"},
+                        {"fixed": "2.1.0"}
+                    ]
+                }]
+            })
+        );
+    }
+
+    #[test]
+    fn bound_styles() {
+        let unaffected = reqs(&["< 1.0.0", ">= 1.2.0, < 2.0.0", "> 2.1.0"]);
+        let published: Vec<_> = ["1.0.0", "1.1.3", "1.2.0", "2.1.0", "2.1.1"]
+            .iter()
+            .map(|v| Version::parse(v).unwrap())
+            .collect();
+
+        let affected =
+            OsvAffected::from_unaffected_in_style(&unaffected, OsvBoundStyle::Fixed, &published);
+        assert_eq!(
+            serde_json::to_value(&affected.ranges[0].events).unwrap(),
+            json!([
+                {"introduced": "1.0.0"},
+                {"fixed": "1.2.0"},
+                {"introduced": "2.0.0"},
+                {"fixed": "2.1.1"},
+            ])
+        );
+
+        let affected = OsvAffected::from_unaffected_in_style(
+            &unaffected,
+            OsvBoundStyle::LastAffected,
+            &published,
+        );
+        assert_eq!(
+            serde_json::to_value(&affected.ranges[0].events).unwrap(),
+            json!([
+                {"introduced": "1.0.0"},
+                {"last_affected": "1.1.3"},
+                {"introduced": "2.0.0"},
+                {"last_affected": "2.1.0"},
+            ])
+        );
+    }
+
+    #[test]
+    fn yanked_fixes() {
+        let unaffected = reqs(&[">= 1.2.0, < 2.0.0", ">= 2.1.0"]);
+        let yanked = [Version::new(1, 1, 0), Version::new(2, 1, 0)];
+        let affected = OsvAffected::from_unaffected(&unaffected).with_yanked_fixes(&yanked);
+
+        assert_eq!(
+            serde_json::to_value(affected).unwrap(),
+            json!({
+                "ranges": [{
+                    "type": "SEMVER",
+                    "events": [
+                        {"introduced": "0.0.0"},
+                        {"fixed": "1.2.0"},
+                        {"introduced": "2.0.0"},
+                        {"fixed": "2.1.0"},
+                    ],
+                    "database_specific": {"yanked_fixes": ["2.1.0"]},
+                }],
+            })
+        );
+
+        // Nothing to note if none of the fixes were yanked
+        let affected = OsvAffected::from_unaffected(&unaffected);
+        assert_eq!(affected.clone().with_yanked_fixes(&yanked[..1]), affected);
+    }
+
+    #[test]
+    fn inexpressible_requirements() {
+        let unaffected = reqs(&[">= 2.0.0, < 1.0.0", ">= 3.0.0"]);
+        let affected = OsvAffected::from_unaffected(&unaffected);
+
+        assert!(affected.ranges.is_empty());
+        assert_eq!(
+            serde_json::to_value(&affected).unwrap(),
+            json!({
+                "ecosystem_specific": {
+                    "unaffected": [unaffected[0].to_string(), unaffected[1].to_string()]
+                }
+            })
+        );
+    }
+
+    #[test]
+    fn no_fixed_version() {
+        let affected = OsvAffected::from_unaffected(&[]);
+
+        assert_eq!(
+            serde_json::to_value(affected).unwrap(),
+            json!({
+                "ranges": [{
+                    "type": "SEMVER",
+                    "events": [{"introduced": "0.0.0"}]
+                }]
+            })
+        );
+    }
+
+    #[test]
+    fn exclusive_unaffected_start() {
+        // `1.0.0` itself is affected, so it must not be reported as fixed
+        let affected = OsvAffected::from_unaffected(&reqs(&["> 1.0.0"]));
+
+        assert_eq!(
+            serde_json::to_value(affected).unwrap(),
+            json!({
+                "ranges": [{
+                    "type": "SEMVER",
+                    "events": [
+                        {"introduced": "0.0.0"},
+                        {"last_affected": "1.0.0"}
+                    ]
+                }]
+            })
+        );
+    }
+
+    #[test]
+    fn half_open_unaffected_ranges() {
+        // Everything from the unaffected start up is safe, which ends the
+        // single affected range there
+        let from = OsvAffected::from_unaffected(&reqs(&[">= 1.5.0"]));
+        assert_eq!(
+            serde_json::to_value(from).unwrap(),
+            json!({
+                "ranges": [{
+                    "type": "SEMVER",
+                    "events": [
+                        {"introduced": "0.0.0"},
+                        {"fixed": "1.5.0"}
+                    ]
+                }]
+            })
+        );
+
+        // Everything up to the unaffected end is safe, and the affected range
+        // stays open at the top
+        let up_to = OsvAffected::from_unaffected(&reqs(&["<= 1.5.0"]));
+        assert_eq!(
+            serde_json::to_value(up_to).unwrap(),
+            json!({
+                "ranges": [{
+                    "type": "SEMVER",
+                    "events": [{"introduced": "1.5.1-0"}]
+                }]
+            })
+        );
+    }
+
+    #[test]
+    fn full_coverage() {
+        for unaffected in &[vec!["< 1.0.0", ">= 1.0.0"], vec!["*"]] {
+            let affected = OsvAffected::from_unaffected(&reqs(unaffected));
+
+            assert!(affected.ranges.is_empty());
+            assert_eq!(serde_json::to_value(affected).unwrap(), json!({}));
+        }
+    }
+}
@@ -0,0 +1,120 @@
+//! Errors related to version ranges
+
+use super::{OsvRange, UnaffectedRange};
+use semver::ReqParseError;
+use std::fmt;
+use thiserror::Error;
+
+/// Errors which can occur when converting version requirements into ranges
+#[derive(Clone, Debug, Error, PartialEq)]
+#[non_exhaustive]
+pub enum RangeError {
+    /// The range has more than two predicates, i.e. more than a single
+    /// lower and upper bound
+    #[error("too many predicates")]
+    TooManyPredicates,
+
+    /// The range has more than one lower bound
+    #[error("multiple lower bounds")]
+    MultipleLowerBounds,
+
+    /// The range has more than one upper bound
+    #[error("multiple upper bounds")]
+    MultipleUpperBounds,
+
+    /// An exact version (`=x`) is combined with other predicates in the same
+    /// range
+    #[error("exact version combined with other predicates")]
+    ExactVersionWithOtherPredicates,
+
+    /// The start of the range lies above its end
+    #[error("invalid range: start is above end")]
+    InvalidRange,
+
+    /// The range does not contain any versions
+    #[error("empty range")]
+    EmptyRange,
+
+    /// Two unaffected ranges overlap, which usually indicates a mistake in
+    /// the advisory
+    #[error("unaffected ranges {0} and {1} overlap")]
+    OverlappingRanges(Box<UnaffectedRange>, Box<UnaffectedRange>),
+
+    /// The text isn't a range in interval notation, e.g. `[1.0.0, 2.0.0)`
+    #[error("malformed range: {0}")]
+    MalformedRange(String),
+
+    /// Two affected ranges overlap, or are not sorted in increasing order
+    #[error("affected ranges {0} and {1} overlap or are out of order")]
+    UnorderedRanges(Box<OsvRange>, Box<OsvRange>),
+
+    /// The version requirement consists of several `||`-separated ranges
+    /// where a single one was expected
+    #[error("multiple ranges where one was expected")]
+    MultipleRanges,
+
+    /// The range doesn't state both of its bounds (or an exact version), so
+    /// the missing side would be treated as unbounded, or it's written with
+    /// an operator such as `^` which implies its bounds
+    #[error("range is missing an explicit lower or upper bound")]
+    ImplicitBound,
+
+    /// The comparator uses an operator which can't be converted into bounds
+    #[error("unsupported comparator: {0}")]
+    UnsupportedComparator(String),
+
+    /// The version requirement couldn't be parsed
+    #[error("invalid version requirement: {0}")]
+    InvalidRequirement(#[source] ReqParseError),
+
+    /// Converting the given requirement into ranges failed
+    #[error("{source} in \"{req}\"")]
+    Requirement {
+        /// The requirement which couldn't be converted
+        req: String,
+
+        /// Why the conversion failed
+        source: Box<RangeError>,
+    },
+}
+
+// `ReqParseError` only implements `PartialEq`, but its equality is total all
+// the same
+impl Eq for RangeError {}
+
+impl RangeError {
+    /// Attach the requirement which caused this error
+    pub(crate) fn in_requirement(self, req: impl fmt::Display) -> RangeError {
+        RangeError::Requirement {
+            req: req.to_string(),
+            source: Box::new(self),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RangeError;
+    use crate::osv::UnaffectedRange;
+    use semver::{ReqParseError, VersionReq};
+    use std::error::Error;
+
+    #[test]
+    fn source_chaining() {
+        let err = "not a version".parse::<UnaffectedRange>().unwrap_err();
+        let boxed: Box<dyn Error + Send + Sync + 'static> = Box::new(err.clone());
+
+        let range_error = boxed.downcast_ref::<RangeError>().unwrap();
+        assert_eq!(range_error, &err);
+        assert!(range_error
+            .source()
+            .unwrap()
+            .downcast_ref::<ReqParseError>()
+            .is_some());
+
+        let err = RangeError::MultipleLowerBounds
+            .in_requirement(">= 1, >= 2".parse::<VersionReq>().unwrap());
+        assert_eq!(err.source().unwrap().to_string(), "multiple lower bounds");
+        assert!(RangeError::MultipleLowerBounds.source().is_none());
+    }
+}
@@ -0,0 +1,41 @@
+//! Events in the timeline of an OSV range
+
+use semver::Version;
+use serde::{Deserialize, Serialize};
+
+/// An event in the timeline of an OSV `SEMVER` range, serialized as e.g.
+/// `{"introduced": "1.0.0"}`, `{"fixed": "1.2.0"}` or
+/// `{"last_affected": "1.1.0"}`
+#[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OsvEvent {
+    /// The vulnerability was introduced in this version
+    Introduced(Version),
+
+    /// The vulnerability was fixed in this version
+    Fixed(Version),
+
+    /// This is the last version affected by the vulnerability, but the next
+    /// one isn't known to contain a fix
+    LastAffected(Version),
+}
+
+/// The version in the `introduced` event of ranges which are unbounded
+/// below, since OSV has no way to say "from the beginning" other than the
+/// lowest version.
+///
+/// There is no such sentinel for ranges which are unbounded above: those
+/// have no `fixed` (or `last_affected`) event at all.
+pub fn osv_introduced_floor() -> Version {
+    Version::new(0, 0, 0)
+}
+
+/// How to express the upper boundary of an exported OSV range
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
+pub enum OsvBoundStyle {
+    /// The first version which isn't affected, i.e. a `fixed` event
+    Fixed,
+
+    /// The last version which is affected, i.e. a `last_affected` event
+    LastAffected,
+}
@@ -0,0 +1,74 @@
+//! Matching versions against requirements the way Cargo does
+
+use semver::{Version, VersionReq};
+
+/// Does the given version match the requirement, according to the rules
+/// Cargo uses when resolving dependencies?
+///
+/// The version has to lie within all bounds of at least one of the
+/// `||`-separated ranges of the requirement. Additionally, pre-releases are
+/// only matched by ranges which opt into them: at least one comparator of the
+/// range has to refer to a pre-release of the same `major.minor.patch`
+/// version. So `>= 1.0.0-alpha` matches `1.0.0-beta`, but neither it nor
+/// `>= 1.0.0` match `1.1.0-alpha`. These are the rules `VersionReq::matches`
+/// implements, so this is the same as calling it directly.
+///
+/// Unlike the ranges used for computing affected versions, which treat
+/// pre-releases as regular points on the version line, this tells whether
+/// Cargo would ever select the given version for the requirement.
+pub fn cargo_matches(req: &VersionReq, version: &Version) -> bool {
+    req.matches(version)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::cargo_matches;
+    use semver::{Version, VersionReq};
+
+    fn matches(req: &str, version: &str) -> bool {
+        cargo_matches(
+            &VersionReq::parse(req).unwrap(),
+            &Version::parse(version).unwrap(),
+        )
+    }
+
+    #[test]
+    fn releases() {
+        assert!(matches(">= 1.0.0", "1.0.0"));
+        assert!(matches(">= 1.0.0", "2.5.1"));
+        assert!(!matches(">= 1.0.0", "0.9.9"));
+        assert!(matches("^1.2.3", "1.9.0"));
+        assert!(!matches("^1.2.3", "2.0.0"));
+        assert!(matches("~1.2", "1.2.9"));
+        assert!(!matches("~1.2", "1.3.0"));
+        assert!(matches("^0.1.2", "0.1.5"));
+        assert!(!matches("^0.1.2", "0.2.0"));
+        assert!(matches("= 1.2.3", "1.2.3"));
+        assert!(matches("*", "3.1.4"));
+        assert!(matches("< 1.0.0 || >= 2.0.0", "2.0.0"));
+        assert!(!matches("< 1.0.0 || >= 2.0.0", "1.5.0"));
+    }
+
+    #[test]
+    fn prereleases() {
+        // Pre-releases are only matched when the requirement opts into them
+        assert!(!matches(">= 1.0.0", "1.1.0-alpha"));
+        assert!(!matches("*", "1.0.0-alpha"));
+        assert!(!matches("< 2.0.0", "2.0.0-alpha"));
+        assert!(!matches("^1.2.3", "1.3.0-alpha"));
+
+        assert!(matches(">= 1.0.0-alpha", "1.0.0-beta"));
+        assert!(!matches(">= 1.0.0-alpha", "1.0.1-alpha"));
+        assert!(matches(">= 1.0.0-alpha", "1.0.1"));
+        assert!(matches("^1.2.3-alpha", "1.2.3-beta"));
+        assert!(!matches("^1.2.3-alpha", "1.2.4-alpha"));
+        assert!(matches("^1.2.3-alpha", "1.2.4"));
+        assert!(!matches("^1.2.3-beta", "1.2.3-alpha"));
+        assert!(matches("= 1.2.3-rc.1", "1.2.3-rc.1"));
+        assert!(!matches("= 1.2.3-rc.1", "1.2.3-rc.2"));
+
+        // Only the range containing the pre-release comparator opts in
+        assert!(matches("< 1.0.0 || >= 2.0.0-rc.1", "2.0.0-rc.2"));
+        assert!(!matches("< 1.0.0-rc.1 || >= 2.0.0", "2.0.0-rc.2"));
+    }
+}
@@ -0,0 +1,1109 @@
+//! Ranges of affected versions, as defined by the OSV format
+
+use super::{event::osv_introduced_floor, ranges::increment, OsvBoundStyle, OsvEvent, RangeError};
+use semver::Version;
+use std::{cmp::Ordering, fmt, str::FromStr};
+
+#[cfg(feature = "osv")]
+use {
+    serde::{Deserialize, Serialize},
+    std::convert::TryFrom,
+};
+
+/// A range of affected versions.
+///
+/// If any of the bounds is unspecified, that means ALL versions
+/// in that direction are affected.
+///
+/// This format is defined by <https://github.com/google/osv>
+///
+/// With the `osv` feature enabled, serializes as an object with the
+/// `introduced` and either the `fixed` or `last_affected` versions, e.g.
+/// `{"introduced": "1.0.0", "fixed": "1.2.0"}`, omitting the fields for
+/// unbounded sides.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "osv", derive(Deserialize, Serialize))]
+#[cfg_attr(
+    feature = "osv",
+    serde(try_from = "OsvRangeFields", into = "OsvRangeFields")
+)]
+pub struct OsvRange {
+    /// Inclusive
+    pub(super) start: Option<Version>,
+
+    /// Exclusive
+    pub(super) end: Option<Version>,
+
+    /// Inclusive end, if the range was derived from an exclusive unaffected
+    /// bound. In that case `end` is the lowest version above this one.
+    pub(super) last_affected: Option<Version>,
+}
+
+impl OsvRange {
+    /// Create a new range of affected versions from its inclusive start and
+    /// exclusive end, where `None` means the range is unbounded on that side.
+    ///
+    /// Returns [`RangeError::InvalidRange`] if `start` is above `end`, and
+    /// [`RangeError::EmptyRange`] if they are equal.
+    ///
+    /// Build metadata has no bearing on semver precedence, so it is stripped
+    /// from both bounds.
+    pub fn new(start: Option<Version>, end: Option<Version>) -> Result<Self, RangeError> {
+        let start = start.map(without_build);
+        let end = end.map(without_build);
+
+        if let (Some(start), Some(end)) = (&start, &end) {
+            if start > end {
+                return Err(RangeError::InvalidRange);
+            }
+
+            if start == end {
+                return Err(RangeError::EmptyRange);
+            }
+        }
+
+        Ok(OsvRange {
+            start,
+            end,
+            last_affected: None,
+        })
+    }
+
+    /// Create a range containing all versions
+    pub fn unbounded() -> Self {
+        OsvRange {
+            start: None,
+            end: None,
+            last_affected: None,
+        }
+    }
+
+    /// Create a range containing all versions starting at the given one
+    pub fn from_inclusive(start: Version) -> Self {
+        OsvRange {
+            start: Some(without_build(start)),
+            end: None,
+            last_affected: None,
+        }
+    }
+
+    /// Get the first affected version (inclusive).
+    ///
+    /// `None` means all versions below the end of this range are affected.
+    pub fn start(&self) -> Option<&Version> {
+        self.start.as_ref()
+    }
+
+    /// Get the first version above the start of this range which is no
+    /// longer affected (exclusive).
+    ///
+    /// `None` means all versions above the start of this range are affected.
+    pub fn end(&self) -> Option<&Version> {
+        self.end.as_ref()
+    }
+
+    /// Get the last affected version (inclusive), if this range is known to
+    /// end at a specific version rather than at a fix.
+    ///
+    /// This is the case when the version above it is only known to be
+    /// unaffected because of an exclusive bound, such as `> 1.0.0`, in which
+    /// case [`OsvRange::end`] is merely the lowest version above this one.
+    pub fn last_affected(&self) -> Option<&Version> {
+        self.last_affected.as_ref()
+    }
+
+    /// Is the given version within this range?
+    ///
+    /// Versions are compared using semver precedence, so pre-releases are
+    /// treated like any other version: `1.0.0-beta` is within a range starting
+    /// at `1.0.0-alpha`, but not within one starting at `1.0.0`.
+    pub fn contains(&self, version: &Version) -> bool {
+        self.position(version) == Position::Within
+    }
+
+    /// Is the given version below, within or above this range?
+    ///
+    /// Versions are compared like in [`OsvRange::contains`]. Since sorted,
+    /// disjoint ranges are ordered the same way as the versions within them,
+    /// this allows binary searching a list of ranges for a version.
+    pub fn position(&self, version: &Version) -> Position {
+        if self.start.as_ref().map_or(false, |start| version < start) {
+            Position::Below
+        } else if self.end.as_ref().map_or(false, |end| version >= end) {
+            Position::Above
+        } else {
+            Position::Within
+        }
+    }
+
+    /// Raise the start of this range to the given floor (inclusive), if it is
+    /// unbounded or lies below it.
+    ///
+    /// Returns `None` if the whole range lies below the floor.
+    pub fn clamp_start(mut self, floor: &Version) -> Option<OsvRange> {
+        if self.end.as_ref().map_or(false, |end| end <= floor) {
+            return None;
+        }
+
+        if self.start.as_ref().map_or(true, |start| start < floor) {
+            self.start = Some(without_build(floor.clone()));
+        }
+
+        Some(self)
+    }
+
+    /// Replace an unbounded start with an explicit `0.0.0`, OSV's way of
+    /// saying "from the beginning".
+    ///
+    /// Note that the result no longer contains pre-releases of `0.0.0`, which
+    /// lie below it.
+    pub fn with_explicit_start(mut self) -> Self {
+        if self.start.is_none() {
+            self.start = Some(osv_introduced_floor());
+        }
+
+        self
+    }
+
+    /// Get the range of versions contained in both this range and the other
+    /// one, if there are any.
+    ///
+    /// The result ends like whichever of the two ranges ends first,
+    /// preferring this one if they end at the same version.
+    pub fn intersection(&self, other: &OsvRange) -> Option<OsvRange> {
+        let start = std::cmp::max(&self.start, &other.start).clone();
+
+        let end_first = match (&self.end, &other.end) {
+            (Some(end), Some(other_end)) => end <= other_end,
+            (Some(_), None) | (None, None) => true,
+            (None, Some(_)) => false,
+        };
+        let ending = if end_first { self } else { other };
+
+        let result = OsvRange {
+            start,
+            end: ending.end.clone(),
+            last_affected: ending.last_affected.clone(),
+        };
+
+        match (&result.start, &result.end) {
+            (Some(start), Some(end)) if start >= end => None,
+            _ => Some(result),
+        }
+    }
+
+    /// Do this range and the other one have any versions in common?
+    ///
+    /// Ranges which only touch, i.e. where one ends right where the other
+    /// starts, don't overlap.
+    pub fn overlaps(&self, other: &OsvRange) -> bool {
+        let starts_before_end =
+            |start: &Option<Version>, range: &OsvRange| match (start, &range.end) {
+                (Some(start), Some(end)) => start < end,
+                _ => true,
+            };
+
+        starts_before_end(&self.start, other) && starts_before_end(&other.start, self)
+    }
+
+    /// Split this range at the given version into the part below it and the
+    /// part starting at it.
+    ///
+    /// If the pivot lies outside of the range, the whole range ends up on
+    /// one side and the other is `None`. The lower part ends at the pivot,
+    /// which is not a boundary of the advisory, so it's treated as fixed
+    /// there; the upper part keeps the end of this range.
+    pub fn split_at(self, pivot: &Version) -> (Option<OsvRange>, Option<OsvRange>) {
+        match self.position(pivot) {
+            Position::Below => (None, Some(self)),
+            Position::Above => (Some(self), None),
+            Position::Within if self.start.as_ref() == Some(pivot) => (None, Some(self)),
+            Position::Within => {
+                let pivot = without_build(pivot.clone());
+
+                let lower = OsvRange {
+                    start: self.start,
+                    end: Some(pivot.clone()),
+                    last_affected: None,
+                };
+                let upper = OsvRange {
+                    start: Some(pivot),
+                    ..self
+                };
+
+                (Some(lower), Some(upper))
+            }
+        }
+    }
+
+    /// Get the versions this range is bounded by: its start (the first
+    /// affected version) and its end (the first version above it which is
+    /// no longer affected), if the range is bounded on that side.
+    ///
+    /// This is useful for probing containment at the edges of the range, but
+    /// is not an enumeration of all versions it contains.
+    pub fn boundary_versions(&self) -> Vec<Version> {
+        self.start.iter().chain(&self.end).cloned().collect()
+    }
+
+    /// Convert this range into the list of events describing it in an OSV
+    /// `SEMVER` range.
+    ///
+    /// An unbounded start is introduced at `0.0.0`, while an unbounded end
+    /// omits the `fixed` event altogether. Ranges with an inclusive end are
+    /// closed with a `last_affected` event instead of `fixed`.
+    pub fn to_osv_events(&self) -> Vec<OsvEvent> {
+        let introduced = self.start.clone().unwrap_or_else(osv_introduced_floor);
+        let mut events = vec![OsvEvent::Introduced(introduced)];
+        events.extend(self.end_event());
+        events
+    }
+
+    /// Get the event closing this range, unless it's unbounded above
+    fn end_event(&self) -> Option<OsvEvent> {
+        if let Some(last_affected) = &self.last_affected {
+            return Some(OsvEvent::LastAffected(last_affected.clone()));
+        }
+
+        self.end.clone().map(OsvEvent::Fixed)
+    }
+
+    /// Convert this range into OSV events like [`OsvRange::to_osv_events`],
+    /// expressing the upper boundary in the given style.
+    ///
+    /// Converting between the styles needs the adjacent published version,
+    /// which is looked up in the given sorted list of published versions.
+    /// This is best-effort: e.g. `fixed: 1.2.0` can only be turned into
+    /// `last_affected` if a version below `1.2.0` within the range was
+    /// published. If there is no such version, the end is kept as-is. In the
+    /// other direction the lowest version above the last affected one is used
+    /// if no later version was published, i.e. `last_affected: 1.2.3`
+    /// becomes `fixed: 1.2.4-0`.
+    pub fn to_osv_events_in_style(
+        &self,
+        style: OsvBoundStyle,
+        published: &[Version],
+    ) -> Vec<OsvEvent> {
+        let mut events = self.to_osv_events();
+
+        let converted = match (style, &self.last_affected, &self.end) {
+            (OsvBoundStyle::Fixed, Some(last_affected), Some(end)) => {
+                let end = published
+                    .iter()
+                    .find(|v| *v > last_affected)
+                    .unwrap_or(end)
+                    .clone();
+                Some(OsvEvent::Fixed(end))
+            }
+            (OsvBoundStyle::LastAffected, None, Some(end)) => published
+                .iter()
+                .rev()
+                .find(|v| *v < end)
+                .filter(|v| self.contains(v))
+                .map(|v| OsvEvent::LastAffected(v.clone())),
+            _ => None,
+        };
+
+        if let Some(event) = converted {
+            *events.last_mut().unwrap() = event;
+        }
+
+        events
+    }
+
+    /// Describe the range in plain English, e.g. "versions 1.0.0 up to but
+    /// not including 1.2.0", for use in human-readable output.
+    ///
+    /// Each shape of range is described by a single sentence template.
+    pub fn describe(&self) -> String {
+        match (&self.start, &self.last_affected, &self.end) {
+            (Some(start), Some(last), _) => format!("versions {} through {}", start, last),
+            (Some(start), None, Some(end)) => {
+                format!("versions {} up to but not including {}", start, end)
+            }
+            (Some(start), None, None) => format!("versions {} and above", start),
+            (None, Some(last), _) => format!("all versions up to and including {}", last),
+            (None, None, Some(end)) => format!("all versions below {}", end),
+            (None, None, None) => "all versions".to_owned(),
+        }
+    }
+}
+
+/// Serialized form of an [`OsvRange`]
+#[cfg(feature = "osv")]
+#[derive(Deserialize, Serialize)]
+struct OsvRangeFields {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    introduced: Option<Version>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    fixed: Option<Version>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    last_affected: Option<Version>,
+}
+
+#[cfg(feature = "osv")]
+impl TryFrom<OsvRangeFields> for OsvRange {
+    type Error = RangeError;
+
+    fn try_from(fields: OsvRangeFields) -> Result<Self, RangeError> {
+        match (fields.fixed, fields.last_affected) {
+            (None, Some(last_affected)) => {
+                let mut range = OsvRange::new(fields.introduced, Some(increment(&last_affected)))?;
+                range.last_affected = Some(without_build(last_affected));
+                Ok(range)
+            }
+            (fixed, None) => OsvRange::new(fields.introduced, fixed),
+            _ => Err(RangeError::MultipleUpperBounds),
+        }
+    }
+}
+
+#[cfg(feature = "osv")]
+impl From<OsvRange> for OsvRangeFields {
+    fn from(range: OsvRange) -> Self {
+        let (fixed, last_affected) = match range.end_event() {
+            Some(OsvEvent::LastAffected(last_affected)) => (None, Some(last_affected)),
+            Some(OsvEvent::Fixed(fixed)) => (Some(fixed), None),
+            _ => (None, None),
+        };
+
+        OsvRangeFields {
+            introduced: range.start,
+            fixed,
+            last_affected,
+        }
+    }
+}
+
+/// Strip build metadata from the given version, since it is ignored when
+/// determining precedence and would only get in the way of comparisons
+/// and serialization.
+pub(super) fn without_build(mut version: Version) -> Version {
+    version.build.clear();
+    version
+}
+
+/// Ranges are ordered by their start, then their end, with an unbounded
+/// start below and an unbounded end above all versions. This sorts disjoint
+/// ranges in version order.
+impl Ord for OsvRange {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // `None` sorts first, which is right for the start but not the end
+        let end_key = |range: &OsvRange| (range.end.is_none(), range.end.clone());
+
+        self.start
+            .cmp(&other.start)
+            .then_with(|| end_key(self).cmp(&end_key(other)))
+            .then_with(|| self.last_affected.cmp(&other.last_affected))
+    }
+}
+
+impl PartialOrd for OsvRange {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl fmt::Display for OsvRange {
+    /// Render the range in interval notation, e.g. `[1.0.0, 2.0.0)` or
+    /// `[1.0.0, 1.2.0]` for an inclusive end, with `*` denoting an unbounded
+    /// side
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.start {
+            Some(start) => write!(f, "[{}, ", start)?,
+            None => write!(f, "[*, ")?,
+        }
+
+        match (&self.last_affected, &self.end) {
+            (Some(last_affected), _) => write!(f, "{}]", last_affected),
+            (None, Some(end)) => write!(f, "{})", end),
+            (None, None) => write!(f, "*)"),
+        }
+    }
+}
+
+impl FromStr for OsvRange {
+    type Err = RangeError;
+
+    /// Parse a range in the interval notation produced by `Display`. The
+    /// space after the comma is optional, and an unbounded side may also be
+    /// left empty, e.g. `[1.0.0,2.0.0)` or `[,1.5.0)`.
+    fn from_str(input: &str) -> Result<Self, RangeError> {
+        let malformed = || RangeError::MalformedRange(input.to_owned());
+
+        let inner = input.trim().strip_prefix('[').ok_or_else(malformed)?;
+        let (inner, inclusive_end) = if let Some(inner) = inner.strip_suffix(')') {
+            (inner, false)
+        } else if let Some(inner) = inner.strip_suffix(']') {
+            (inner, true)
+        } else {
+            return Err(malformed());
+        };
+
+        let mut parts = inner.split(',');
+        let (start, end) = match (parts.next(), parts.next(), parts.next()) {
+            (Some(start), Some(end), None) => (start.trim(), end.trim()),
+            _ => return Err(malformed()),
+        };
+
+        let parse = |bound: &str| match bound {
+            "" | "*" => Ok(None),
+            version => Version::parse(version).map(Some).map_err(|_| malformed()),
+        };
+        let start = parse(start)?;
+        let end = parse(end)?;
+
+        if inclusive_end {
+            let last_affected = end.ok_or_else(malformed)?;
+            let mut range = OsvRange::new(start, Some(increment(&last_affected)))?;
+            range.last_affected = Some(without_build(last_affected));
+            Ok(range)
+        } else {
+            OsvRange::new(start, end)
+        }
+    }
+}
+
+/// Position of a version relative to an [`OsvRange`]
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
+pub enum Position {
+    /// The version is below the start of the range
+    Below,
+
+    /// The version is within the range
+    Within,
+
+    /// The version is at or above the (exclusive) end of the range
+    Above,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{osv_introduced_floor, OsvBoundStyle, OsvEvent, OsvRange, Position, RangeError};
+    use semver::Version;
+    use serde_json::json;
+    use std::collections::BTreeSet;
+
+    #[test]
+    fn bound_styles() {
+        let published: Vec<_> = ["1.0.0", "1.1.0", "1.1.5", "1.3.0"]
+            .iter()
+            .map(|v| Version::parse(v).unwrap())
+            .collect();
+        let events = |range: &str, style| {
+            range
+                .parse::<OsvRange>()
+                .unwrap()
+                .to_osv_events_in_style(style, &published)
+        };
+        let v = |s| Version::parse(s).unwrap();
+
+        // Exclusive ends become the last published version below them
+        assert_eq!(
+            events("[1.0.0, 1.2.0)", OsvBoundStyle::LastAffected),
+            vec![
+                OsvEvent::Introduced(v("1.0.0")),
+                OsvEvent::LastAffected(v("1.1.5"))
+            ]
+        );
+        assert_eq!(
+            events("[1.0.0, 1.2.0)", OsvBoundStyle::Fixed),
+            vec![
+                OsvEvent::Introduced(v("1.0.0")),
+                OsvEvent::Fixed(v("1.2.0"))
+            ]
+        );
+
+        // Inclusive ends become the next published version above them
+        assert_eq!(
+            events("[1.0.0, 1.1.0]", OsvBoundStyle::Fixed),
+            vec![
+                OsvEvent::Introduced(v("1.0.0")),
+                OsvEvent::Fixed(v("1.1.5"))
+            ]
+        );
+        assert_eq!(
+            events("[1.0.0, 1.1.0]", OsvBoundStyle::LastAffected),
+            vec![
+                OsvEvent::Introduced(v("1.0.0")),
+                OsvEvent::LastAffected(v("1.1.0"))
+            ]
+        );
+
+        // Without an adjacent published version the end is kept, or the
+        // lowest possible version is used
+        assert_eq!(
+            events("[1.2.0, 1.2.5)", OsvBoundStyle::LastAffected),
+            vec![
+                OsvEvent::Introduced(v("1.2.0")),
+                OsvEvent::Fixed(v("1.2.5"))
+            ]
+        );
+        assert_eq!(
+            events("[1.3.0, 1.3.0]", OsvBoundStyle::Fixed),
+            vec![
+                OsvEvent::Introduced(v("1.3.0")),
+                OsvEvent::Fixed(v("1.3.1-0"))
+            ]
+        );
+
+        // Unbounded ends have nothing to convert
+        assert_eq!(
+            events("[1.0.0, *)", OsvBoundStyle::LastAffected),
+            vec![OsvEvent::Introduced(v("1.0.0"))]
+        );
+    }
+
+    #[test]
+    fn describe() {
+        let describe = |s: &str| s.parse::<OsvRange>().unwrap().describe();
+
+        assert_eq!(
+            describe("[1.0.0, 1.2.0)"),
+            "versions 1.0.0 up to but not including 1.2.0"
+        );
+        assert_eq!(describe("[1.0.0, 1.2.0]"), "versions 1.0.0 through 1.2.0");
+        assert_eq!(describe("[1.0.0, *)"), "versions 1.0.0 and above");
+        assert_eq!(describe("[*, 1.2.0)"), "all versions below 1.2.0");
+        assert_eq!(
+            describe("[*, 1.2.0]"),
+            "all versions up to and including 1.2.0"
+        );
+        assert_eq!(describe("[*, *)"), "all versions");
+    }
+
+    #[test]
+    fn new_validation() {
+        let v1 = Version::new(1, 0, 0);
+        let v2 = Version::new(2, 0, 0);
+
+        assert!(OsvRange::new(Some(v1.clone()), Some(v2.clone())).is_ok());
+        assert!(OsvRange::new(None, Some(v1.clone())).is_ok());
+        assert!(OsvRange::new(Some(v1.clone()), None).is_ok());
+        assert_eq!(OsvRange::new(None, None).unwrap(), OsvRange::unbounded());
+        assert_eq!(
+            OsvRange::new(Some(v2.clone()), None).unwrap(),
+            OsvRange::from_inclusive(v2.clone())
+        );
+
+        assert_eq!(
+            OsvRange::new(Some(v2), Some(v1.clone())),
+            Err(RangeError::InvalidRange)
+        );
+        assert_eq!(
+            OsvRange::new(Some(v1.clone()), Some(v1)),
+            Err(RangeError::EmptyRange)
+        );
+    }
+
+    #[test]
+    fn contains_bounded() {
+        let range = OsvRange {
+            start: Some(Version::new(1, 0, 0)),
+            end: Some(Version::new(2, 0, 0)),
+            last_affected: None,
+        };
+
+        assert!(!range.contains(&Version::new(0, 9, 9)));
+        assert!(range.contains(&Version::new(1, 0, 0)));
+        assert!(range.contains(&Version::new(1, 9, 9)));
+        assert!(!range.contains(&Version::new(2, 0, 0)));
+    }
+
+    #[test]
+    fn contains_unbounded() {
+        let below = OsvRange {
+            start: None,
+            end: Some(Version::new(1, 0, 0)),
+            last_affected: None,
+        };
+        assert!(below.contains(&Version::new(0, 0, 0)));
+        assert!(!below.contains(&Version::new(1, 0, 0)));
+
+        let above = OsvRange {
+            start: Some(Version::new(1, 0, 0)),
+            end: None,
+            last_affected: None,
+        };
+        assert!(!above.contains(&Version::new(0, 9, 9)));
+        assert!(above.contains(&Version::new(1, 0, 0)));
+        assert!(above.contains(&Version::new(100, 0, 0)));
+    }
+
+    #[test]
+    fn position() {
+        let range =
+            OsvRange::new(Some(Version::new(1, 0, 0)), Some(Version::new(2, 0, 0))).unwrap();
+
+        assert_eq!(range.position(&Version::new(0, 9, 0)), Position::Below);
+        assert_eq!(
+            range.position(&Version::parse("1.0.0-rc.1").unwrap()),
+            Position::Below
+        );
+        assert_eq!(range.position(&Version::new(1, 0, 0)), Position::Within);
+        assert_eq!(range.position(&Version::new(1, 5, 0)), Position::Within);
+        assert_eq!(
+            range.position(&Version::parse("2.0.0-alpha").unwrap()),
+            Position::Within
+        );
+        assert_eq!(range.position(&Version::new(2, 0, 0)), Position::Above);
+        assert_eq!(range.position(&Version::new(3, 0, 0)), Position::Above);
+
+        let unbounded = OsvRange::unbounded();
+        assert_eq!(unbounded.position(&Version::new(0, 0, 0)), Position::Within);
+    }
+
+    #[test]
+    fn explicit_start() {
+        let below = OsvRange::new(None, Some(Version::new(1, 2, 0))).unwrap();
+        assert_eq!(
+            below.with_explicit_start(),
+            OsvRange::new(Some(Version::new(0, 0, 0)), Some(Version::new(1, 2, 0))).unwrap()
+        );
+
+        let above = OsvRange::from_inclusive(Version::new(1, 0, 0));
+        assert_eq!(above.clone().with_explicit_start(), above);
+    }
+
+    #[test]
+    fn ordering() {
+        let v = |s: &str| Some(Version::parse(s).unwrap());
+        let ranges = vec![
+            OsvRange::new(v("2.0.0"), None).unwrap(),
+            OsvRange::new(v("1.0.0"), v("1.5.0")).unwrap(),
+            OsvRange::unbounded(),
+            OsvRange::new(v("1.0.0"), None).unwrap(),
+            OsvRange::new(None, v("0.5.0")).unwrap(),
+            OsvRange::new(v("1.0.0"), v("1.2.0")).unwrap(),
+            OsvRange::new(v("1.0.0-rc.1"), v("1.0.0")).unwrap(),
+            OsvRange::new(v("1.0.0"), v("1.2.0")).unwrap(),
+        ];
+
+        let set: BTreeSet<_> = ranges.into_iter().collect();
+        assert_eq!(
+            set.into_iter().map(|r| r.to_string()).collect::<Vec<_>>(),
+            vec![
+                "[*, 0.5.0)",
+                "[*, *)",
+                "[1.0.0-rc.1, 1.0.0)",
+                "[1.0.0, 1.2.0)",
+                "[1.0.0, 1.5.0)",
+                "[1.0.0, *)",
+                "[2.0.0, *)",
+            ]
+        );
+    }
+
+    #[test]
+    fn overlaps() {
+        let range = |s: &str| s.parse::<OsvRange>().unwrap();
+        let check = |a, b, expected| {
+            assert_eq!(range(a).overlaps(&range(b)), expected, "{} and {}", a, b);
+            assert_eq!(range(b).overlaps(&range(a)), expected, "{} and {}", b, a);
+        };
+
+        // Touching
+        check("[1.0.0, 2.0.0)", "[2.0.0, 3.0.0)", false);
+        check("[*, 2.0.0)", "[2.0.0, *)", false);
+        check("[1.0.0, 2.0.0]", "[2.0.0, 3.0.0)", true);
+
+        // Nested
+        check("[1.0.0, 3.0.0)", "[1.5.0, 2.0.0)", true);
+        check("[*, *)", "[1.5.0, 2.0.0)", true);
+        check("[*, 3.0.0)", "[*, 2.0.0)", true);
+
+        // Disjoint
+        check("[1.0.0, 2.0.0)", "[2.5.0, 3.0.0)", false);
+        check("[*, 1.0.0)", "[2.5.0, *)", false);
+
+        // Partially overlapping
+        check("[1.0.0, 2.0.0)", "[1.5.0, *)", true);
+    }
+
+    #[test]
+    fn intersection() {
+        let v = |s: &str| Some(Version::parse(s).unwrap());
+        let range = OsvRange::new(v("1.0.0"), v("2.0.0")).unwrap();
+
+        assert_eq!(
+            range.intersection(&OsvRange::new(v("1.5.0"), None).unwrap()),
+            Some(OsvRange::new(v("1.5.0"), v("2.0.0")).unwrap())
+        );
+        assert_eq!(
+            range.intersection(&OsvRange::new(None, v("1.5.0")).unwrap()),
+            Some(OsvRange::new(v("1.0.0"), v("1.5.0")).unwrap())
+        );
+        assert_eq!(
+            range.intersection(&OsvRange::unbounded()),
+            Some(range.clone())
+        );
+        assert_eq!(
+            OsvRange::unbounded().intersection(&range),
+            Some(range.clone())
+        );
+        assert_eq!(
+            range.intersection(&OsvRange::new(v("2.0.0"), None).unwrap()),
+            None
+        );
+        assert_eq!(
+            range.intersection(&OsvRange::new(None, v("0.5.0")).unwrap()),
+            None
+        );
+
+        // An inclusive end is kept if it comes first
+        let through = OsvRange {
+            start: None,
+            end: v("1.2.1-0"),
+            last_affected: v("1.2.0"),
+        };
+        assert_eq!(
+            range.intersection(&through),
+            Some(OsvRange {
+                start: v("1.0.0"),
+                ..through
+            })
+        );
+    }
+
+    #[test]
+    fn split_at() {
+        let v = |s: &str| Version::parse(s).unwrap();
+        let range = OsvRange::new(Some(v("1.0.0")), Some(v("2.0.0"))).unwrap();
+
+        assert_eq!(
+            range.clone().split_at(&v("1.5.0")),
+            (
+                Some(OsvRange::new(Some(v("1.0.0")), Some(v("1.5.0"))).unwrap()),
+                Some(OsvRange::new(Some(v("1.5.0")), Some(v("2.0.0"))).unwrap())
+            )
+        );
+        assert_eq!(
+            range.clone().split_at(&v("1.0.0")),
+            (None, Some(range.clone()))
+        );
+        assert_eq!(
+            range.clone().split_at(&v("2.0.0")),
+            (Some(range.clone()), None)
+        );
+        assert_eq!(
+            range.clone().split_at(&v("0.1.0")),
+            (None, Some(range.clone()))
+        );
+        assert_eq!(range.clone().split_at(&v("3.0.0")), (Some(range), None));
+
+        // Build metadata of the pivot is ignored
+        assert_eq!(
+            OsvRange::from_inclusive(v("1.0.0")).split_at(&v("1.5.0+build")),
+            (
+                Some(OsvRange::new(Some(v("1.0.0")), Some(v("1.5.0"))).unwrap()),
+                Some(OsvRange::new(Some(v("1.5.0")), None).unwrap())
+            )
+        );
+
+        // The upper part keeps an inclusive end
+        let last_affected = OsvRange {
+            start: None,
+            end: Some(v("1.2.1-0")),
+            last_affected: Some(v("1.2.0")),
+        };
+        let (lower, upper) = last_affected.split_at(&v("1.0.0"));
+        assert_eq!(lower, Some(OsvRange::new(None, Some(v("1.0.0"))).unwrap()));
+        let upper = upper.unwrap();
+        assert_eq!(upper.start(), Some(&v("1.0.0")));
+        assert_eq!(upper.last_affected(), Some(&v("1.2.0")));
+    }
+
+    #[test]
+    fn contains_prerelease() {
+        let beta = Version::parse("1.0.0-beta").unwrap();
+
+        let from_release = OsvRange {
+            start: Some(Version::new(1, 0, 0)),
+            end: None,
+            last_affected: None,
+        };
+        assert!(!from_release.contains(&beta));
+
+        let from_alpha = OsvRange {
+            start: Some(Version::parse("1.0.0-alpha").unwrap()),
+            end: Some(Version::new(1, 0, 0)),
+            last_affected: None,
+        };
+        assert!(from_alpha.contains(&beta));
+        assert!(!from_alpha.contains(&Version::new(1, 0, 0)));
+    }
+
+    #[test]
+    fn osv_events() {
+        let bounded = OsvRange {
+            start: Some(Version::new(1, 0, 0)),
+            end: Some(Version::new(1, 2, 0)),
+            last_affected: None,
+        };
+        assert_eq!(
+            serde_json::to_value(bounded.to_osv_events()).unwrap(),
+            json!([{"introduced": "1.0.0"}, {"fixed": "1.2.0"}])
+        );
+
+        let unbounded_start = OsvRange {
+            start: None,
+            end: Some(Version::new(1, 2, 0)),
+            last_affected: None,
+        };
+        assert_eq!(
+            serde_json::to_value(unbounded_start.to_osv_events()).unwrap(),
+            json!([{"introduced": "0.0.0"}, {"fixed": "1.2.0"}])
+        );
+
+        let unbounded_end = OsvRange {
+            start: Some(Version::parse("2.0.0-alpha.1").unwrap()),
+            end: None,
+            last_affected: None,
+        };
+        let json = json!([{"introduced": "2.0.0-alpha.1"}]);
+        assert_eq!(
+            serde_json::to_value(unbounded_end.to_osv_events()).unwrap(),
+            json
+        );
+        assert_eq!(
+            serde_json::from_value::<Vec<OsvEvent>>(json).unwrap(),
+            unbounded_end.to_osv_events()
+        );
+    }
+
+    #[test]
+    fn unbounded_osv_events() {
+        assert_eq!(
+            serde_json::to_value(OsvRange::unbounded().to_osv_events()).unwrap(),
+            json!([{"introduced": "0.0.0"}])
+        );
+        assert_eq!(
+            OsvRange::unbounded().with_explicit_start().start(),
+            Some(&osv_introduced_floor())
+        );
+    }
+
+    #[test]
+    fn display() {
+        let v1 = Version::new(1, 0, 0);
+        let v2 = Version::new(2, 0, 0);
+
+        assert_eq!(
+            OsvRange::new(Some(v1.clone()), Some(v2))
+                .unwrap()
+                .to_string(),
+            "[1.0.0, 2.0.0)"
+        );
+        assert_eq!(
+            OsvRange::new(None, Some(Version::new(1, 5, 0)))
+                .unwrap()
+                .to_string(),
+            "[*, 1.5.0)"
+        );
+        assert_eq!(OsvRange::from_inclusive(v1).to_string(), "[1.0.0, *)");
+        assert_eq!(OsvRange::unbounded().to_string(), "[*, *)");
+    }
+
+    #[test]
+    fn from_str() {
+        let v = |s: &str| Some(Version::parse(s).unwrap());
+
+        for range in &[
+            OsvRange::new(v("1.0.0"), v("2.0.0")).unwrap(),
+            OsvRange::new(None, v("1.5.0")).unwrap(),
+            OsvRange::new(v("1.0.0-alpha.1"), None).unwrap(),
+            OsvRange::unbounded(),
+            OsvRange {
+                start: v("1.0.0"),
+                end: v("1.2.1-0"),
+                last_affected: v("1.2.0"),
+            },
+        ] {
+            assert_eq!(&range.to_string().parse::<OsvRange>().unwrap(), range);
+        }
+
+        assert_eq!(
+            "[1.0.0,2.0.0)".parse::<OsvRange>().unwrap(),
+            OsvRange::new(v("1.0.0"), v("2.0.0")).unwrap()
+        );
+        assert_eq!(
+            "[,1.5.0)".parse::<OsvRange>().unwrap(),
+            OsvRange::new(None, v("1.5.0")).unwrap()
+        );
+        assert_eq!("[,)".parse::<OsvRange>().unwrap(), OsvRange::unbounded());
+    }
+
+    #[test]
+    fn from_str_errors() {
+        for input in &[
+            "",
+            "1.0.0, 2.0.0",
+            "[1.0.0, 2.0.0",
+            "(1.0.0, 2.0.0)",
+            "[1.0.0)",
+            "[1.0.0, 2.0.0, 3.0.0)",
+            "[1.0, 2.0.0)",
+            "[1.0.0, *]",
+        ] {
+            assert_eq!(
+                input.parse::<OsvRange>(),
+                Err(RangeError::MalformedRange(input.to_string())),
+                "{}",
+                input
+            );
+        }
+
+        assert_eq!(
+            "[2.0.0, 1.0.0)".parse::<OsvRange>(),
+            Err(RangeError::InvalidRange)
+        );
+        assert_eq!(
+            "[1.0.0, 1.0.0)".parse::<OsvRange>(),
+            Err(RangeError::EmptyRange)
+        );
+    }
+
+    #[test]
+    fn build_metadata() {
+        let build_a = Version::parse("1.0.0+a").unwrap();
+        let build_b = Version::parse("1.0.0+b").unwrap();
+
+        let range = OsvRange::new(Some(build_a.clone()), Some(Version::new(2, 0, 0))).unwrap();
+        assert_eq!(range.start(), Some(&Version::new(1, 0, 0)));
+        assert!(range.start().unwrap().build.is_empty());
+        assert!(range.contains(&build_a));
+        assert!(range.contains(&build_b));
+
+        let range = OsvRange::new(None, Some(build_a)).unwrap();
+        assert!(!range.contains(&build_b));
+        assert_eq!(range.to_string(), "[*, 1.0.0)");
+
+        assert_eq!(
+            OsvRange::new(Some(build_b), Some(Version::parse("1.0.0+c").unwrap())),
+            Err(RangeError::EmptyRange)
+        );
+    }
+
+    #[test]
+    fn last_affected() {
+        let range = OsvRange {
+            start: Some(Version::new(1, 0, 0)),
+            end: Some(Version::parse("1.2.1-0").unwrap()),
+            last_affected: Some(Version::new(1, 2, 0)),
+        };
+
+        assert!(range.contains(&Version::new(1, 2, 0)));
+        assert!(!range.contains(&Version::new(1, 2, 1)));
+        assert_eq!(range.to_string(), "[1.0.0, 1.2.0]");
+
+        let json = json!([{"introduced": "1.0.0"}, {"last_affected": "1.2.0"}]);
+        assert_eq!(serde_json::to_value(range.to_osv_events()).unwrap(), json);
+        assert_eq!(
+            serde_json::from_value::<Vec<OsvEvent>>(json).unwrap(),
+            range.to_osv_events()
+        );
+    }
+
+    #[test]
+    fn boundary_versions() {
+        let v1 = Version::new(1, 0, 0);
+        let v2 = Version::new(2, 0, 0);
+
+        let bounded = OsvRange::new(Some(v1.clone()), Some(v2.clone())).unwrap();
+        assert_eq!(bounded.boundary_versions(), vec![v1.clone(), v2.clone()]);
+        assert!(bounded.contains(&bounded.boundary_versions()[0]));
+        assert!(!bounded.contains(&bounded.boundary_versions()[1]));
+
+        let below = OsvRange::new(None, Some(v2.clone())).unwrap();
+        assert_eq!(below.boundary_versions(), vec![v2]);
+
+        assert_eq!(
+            OsvRange::from_inclusive(v1.clone()).boundary_versions(),
+            vec![v1]
+        );
+        assert!(OsvRange::unbounded().boundary_versions().is_empty());
+    }
+
+    #[cfg(feature = "osv")]
+    #[test]
+    fn serde_round_trip() {
+        for (range, json) in &[
+            (
+                OsvRange::new(Some(Version::new(1, 0, 0)), Some(Version::new(1, 2, 0))).unwrap(),
+                json!({"introduced": "1.0.0", "fixed": "1.2.0"}),
+            ),
+            (
+                OsvRange::new(None, Some(Version::new(1, 2, 0))).unwrap(),
+                json!({"fixed": "1.2.0"}),
+            ),
+            (
+                OsvRange::from_inclusive(Version::parse("2.0.0-alpha.1").unwrap()),
+                json!({"introduced": "2.0.0-alpha.1"}),
+            ),
+            (OsvRange::unbounded(), json!({})),
+            (
+                OsvRange {
+                    start: Some(Version::new(1, 0, 0)),
+                    end: Some(Version::parse("1.2.1-0").unwrap()),
+                    last_affected: Some(Version::new(1, 2, 0)),
+                },
+                json!({"introduced": "1.0.0", "last_affected": "1.2.0"}),
+            ),
+        ] {
+            assert_eq!(&serde_json::to_value(range).unwrap(), json);
+            assert_eq!(
+                &serde_json::from_value::<OsvRange>(json.clone()).unwrap(),
+                range
+            );
+        }
+    }
+
+    #[cfg(feature = "osv")]
+    #[test]
+    fn deserialize_invalid() {
+        for json in &[
+            json!({"introduced": "2.0.0", "fixed": "1.0.0"}),
+            json!({"introduced": "1.0.0", "fixed": "1.0.0"}),
+            json!({"fixed": "1.0.0", "last_affected": "1.0.0"}),
+        ] {
+            assert!(serde_json::from_value::<OsvRange>(json.clone()).is_err());
+        }
+    }
+
+    #[test]
+    fn clamp_start() {
+        let floor = Version::new(1, 0, 0);
+        let range = |start: Option<(u64, u64, u64)>, end: Option<(u64, u64, u64)>| {
+            OsvRange::new(start.map(Version::from), end.map(Version::from)).unwrap()
+        };
+
+        // entirely below
+        assert_eq!(range(None, Some((0, 9, 0))).clamp_start(&floor), None);
+        assert_eq!(
+            range(Some((0, 1, 0)), Some((1, 0, 0))).clamp_start(&floor),
+            None
+        );
+
+        // partially below
+        assert_eq!(
+            range(None, Some((1, 5, 0))).clamp_start(&floor),
+            Some(range(Some((1, 0, 0)), Some((1, 5, 0))))
+        );
+        assert_eq!(
+            range(Some((0, 5, 0)), None).clamp_start(&floor),
+            Some(range(Some((1, 0, 0)), None))
+        );
+
+        // entirely above
+        assert_eq!(
+            range(Some((1, 2, 0)), Some((1, 5, 0))).clamp_start(&floor),
+            Some(range(Some((1, 2, 0)), Some((1, 5, 0))))
+        );
+        assert_eq!(
+            range(Some((1, 0, 0)), None).clamp_start(&floor),
+            Some(range(Some((1, 0, 0)), None))
+        );
+    }
+}
@@ -0,0 +1,2390 @@
+//! Computation of the ranges of affected versions from the versions an
+//! advisory lists as patched or unaffected
+
+use super::{
+    event::osv_introduced_floor,
+    range::without_build,
+    unaffected_range::{ranges_from_version_req, Bound, EndBound, StartBound, UnaffectedRange},
+    OsvEvent, OsvRange, Position, RangeError, RangeOrigin,
+};
+use semver::{Identifier, Version, VersionReq};
+use std::{cmp::Ordering, collections::BTreeMap, convert::TryFrom};
+
+#[cfg(feature = "tracing")]
+use {super::unaffected_range::DisplayList, tracing::debug};
+
+/// Compute the ranges of affected versions, given the requirements matching
+/// versions which are not affected (i.e. the `patched` and `unaffected`
+/// fields of an advisory).
+///
+/// Each requirement may consist of several `||`-separated ranges, which are
+/// treated the same as if they were listed separately.
+///
+/// The returned ranges are sorted and disjoint. They cover all versions not
+/// matched by any of the given requirements, including pre-releases, so if
+/// any of the requirements is a wildcard (`*`) nothing is affected and the
+/// result is empty.
+///
+/// Conversely, an advisory without any patched or unaffected versions (i.e.
+/// no fix exists yet) affects every version: the result is a single range
+/// which is unbounded on both ends, and so has no `fixed` event.
+pub fn affected_ranges(unaffected: &[VersionReq]) -> Result<Vec<OsvRange>, RangeError> {
+    let result = complement(&all_unaffected_ranges(unaffected, &[])?);
+    debug_assert_eq!(validate_osv_ranges(&result), Ok(()));
+
+    #[cfg(feature = "tracing")]
+    debug!(affected = ?DisplayList(&result), "computed affected ranges");
+
+    Ok(result)
+}
+
+/// Parse the `patched` and `unaffected` requirements of an advisory into a
+/// single list of unaffected ranges.
+///
+/// Versions matched by either field are not affected, so ranges from both
+/// are merged together: the result is sorted by start bound, and contains
+/// no overlapping, adjacent or duplicate ranges. Each range keeps the
+/// [`RangeOrigin`] of the range it starts with, preferring
+/// [`RangeOrigin::Patched`] if both fields start a range at the same bound.
+pub fn all_unaffected_ranges(
+    patched: &[VersionReq],
+    unaffected: &[VersionReq],
+) -> Result<Vec<UnaffectedRange>, RangeError> {
+    let mut ranges = vec![];
+
+    for req in patched {
+        ranges.extend(ranges_from_version_req(req)?);
+    }
+
+    for req in unaffected {
+        for range in ranges_from_version_req(req)? {
+            ranges.push(range.with_origin(RangeOrigin::Unaffected));
+        }
+    }
+
+    #[cfg(feature = "tracing")]
+    let count = ranges.len();
+    let merged = merge_unaffected(ranges);
+
+    #[cfg(feature = "tracing")]
+    debug!(
+        count,
+        merged = ?DisplayList(&merged),
+        "merged unaffected ranges"
+    );
+
+    Ok(merged)
+}
+
+/// Compute the ranges of affected versions like [`affected_ranges`], but
+/// replace an unbounded start with an explicit `0.0.0` (see
+/// [`OsvRange::with_explicit_start`]) for exporters which need a concrete
+/// `introduced` version.
+pub fn affected_ranges_with_explicit_start(
+    unaffected: &[VersionReq],
+) -> Result<Vec<OsvRange>, RangeError> {
+    Ok(affected_ranges(unaffected)?
+        .into_iter()
+        .map(OsvRange::with_explicit_start)
+        .collect())
+}
+
+/// Compute the ranges of affected versions like [`affected_ranges`], but
+/// only within the given universe of versions, e.g. the span of versions
+/// actually published.
+///
+/// Affected ranges are clamped to the universe, and dropped if they lie
+/// outside of it entirely.
+pub fn affected_ranges_within(
+    unaffected: &[VersionReq],
+    universe: &OsvRange,
+) -> Result<Vec<OsvRange>, RangeError> {
+    Ok(affected_ranges(unaffected)?
+        .iter()
+        .filter_map(|range| range.intersection(universe))
+        .collect())
+}
+
+/// Compute the ranges of affected versions like [`affected_ranges`], along
+/// with whether each range is fixed in a version which has been yanked.
+///
+/// Only the `fixed` boundary is considered: ranges which are unbounded above
+/// or end with a `last_affected` version are never flagged.
+pub fn affected_ranges_with_metadata(
+    unaffected: &[VersionReq],
+    yanked: &[Version],
+) -> Result<Vec<(OsvRange, bool)>, RangeError> {
+    Ok(affected_ranges(unaffected)?
+        .into_iter()
+        .map(|range| {
+            let fixed_yanked = range.last_affected().is_none()
+                && range.end().map_or(false, |fixed| yanked.contains(fixed));
+            (range, fixed_yanked)
+        })
+        .collect())
+}
+
+/// Count the disjoint ranges of affected versions, i.e. the length of the
+/// list returned by [`affected_ranges`], without computing the list itself.
+pub fn affected_interval_count(unaffected: &[VersionReq]) -> Result<usize, RangeError> {
+    let ranges = all_unaffected_ranges(unaffected, &[])?;
+
+    let (first, last) = match (ranges.first(), ranges.last()) {
+        (Some(first), Some(last)) => (first, last),
+        _ => return Ok(1),
+    };
+
+    // One gap between each pair of consecutive unaffected ranges, unless no
+    // version fits into it by precedence (e.g. after `<= 1.0.0` and before
+    // `>= 1.0.1-0`), plus the versions below the first and above the last
+    let gaps = ranges
+        .windows(2)
+        .filter(|pair| match (pair[0].end(), pair[1].start()) {
+            (Bound::Inclusive(end), Bound::Inclusive(start)) => &increment(end) != start,
+            _ => true,
+        })
+        .count();
+    let below = first.start() != &Bound::Unbounded;
+    let above = last.end() != &Bound::Unbounded;
+
+    Ok(gaps + below as usize + above as usize)
+}
+
+/// Compute the versions affected by any of several advisories for the same
+/// crate, given the unaffected requirements of each advisory.
+///
+/// The affected ranges of each advisory are computed like
+/// [`affected_ranges`], then merged with [`merge_affected`].
+pub fn union_affected(
+    advisory_unaffected_sets: &[Vec<VersionReq>],
+) -> Result<Vec<OsvRange>, RangeError> {
+    let mut ranges = vec![];
+
+    for unaffected in advisory_unaffected_sets {
+        ranges.extend(affected_ranges(unaffected)?);
+    }
+
+    Ok(merge_affected(ranges))
+}
+
+/// Check whether two advisories for the same crate affect entirely separate
+/// versions, given the unaffected requirements of each advisory.
+pub fn affected_sets_disjoint(a: &[VersionReq], b: &[VersionReq]) -> Result<bool, RangeError> {
+    let a = affected_ranges(a)?;
+    let b = affected_ranges(b)?;
+
+    Ok(!a.iter().any(|x| b.iter().any(|y| x.overlaps(y))))
+}
+
+/// Find pairs of advisories for the same crate whose affected versions
+/// overlap, which often means that they are duplicates.
+///
+/// Each entry of the database consists of the crate name and the unaffected
+/// requirements of an advisory. Conflicts refer to advisories by their index
+/// in the database, and are reported for every range of versions affected
+/// by both, ordered by those indices. Advisories whose requirements can't be
+/// converted into ranges are skipped; see [`unsupported_requirements`] for
+/// finding those.
+///
+/// This includes overlaps which are unbounded below: two advisories which
+/// both affect every version before their fix (e.g. the same `>= 1.2.0`
+/// listed twice) are reported as conflicting on all versions below the
+/// earlier of the fixes.
+pub fn find_conflicting_advisories(db: &[(&str, Vec<VersionReq>)]) -> Vec<Conflict> {
+    let mut by_crate: BTreeMap<&str, Vec<(usize, Vec<OsvRange>)>> = BTreeMap::new();
+
+    for (index, (name, unaffected)) in db.iter().enumerate() {
+        if let Ok(affected) = affected_ranges(unaffected) {
+            by_crate.entry(name).or_default().push((index, affected));
+        }
+    }
+
+    let mut result = vec![];
+
+    for advisories in by_crate.values() {
+        for (i, (first, a)) in advisories.iter().enumerate() {
+            for (second, b) in &advisories[i + 1..] {
+                for range in a {
+                    result.extend(b.iter().filter_map(|other| {
+                        range.intersection(other).map(|overlap| Conflict {
+                            first: *first,
+                            second: *second,
+                            overlap,
+                        })
+                    }));
+                }
+            }
+        }
+    }
+
+    result.sort_by_key(|conflict| (conflict.first, conflict.second));
+    result
+}
+
+/// Two advisories for the same crate affecting some of the same versions, as
+/// reported by [`find_conflicting_advisories`]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Conflict {
+    /// Index of the first advisory in the database
+    pub first: usize,
+
+    /// Index of the second advisory in the database
+    pub second: usize,
+
+    /// Versions affected by both advisories
+    pub overlap: OsvRange,
+}
+
+/// Check that the given affected ranges are sorted in increasing order and
+/// don't overlap, as OSV consumers expect.
+///
+/// Adjacent ranges (where one starts at the exclusive end of the previous
+/// one) are allowed; [`merge_affected`] combines them if needed.
+pub fn validate_osv_ranges(ranges: &[OsvRange]) -> Result<(), RangeError> {
+    for pair in ranges.windows(2) {
+        let ordered = match (pair[0].end(), pair[1].start()) {
+            (Some(end), Some(start)) => end <= start,
+            _ => false,
+        };
+
+        if !ordered {
+            return Err(RangeError::UnorderedRanges(
+                Box::new(pair[0].clone()),
+                Box::new(pair[1].clone()),
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+/// Merge overlapping and adjacent affected ranges into the minimal
+/// equivalent set of ranges, sorted by start.
+///
+/// Each merged range ends like the range reaching furthest among those it
+/// was merged from, including its `last_affected` version and origin.
+pub fn merge_affected(mut ranges: Vec<OsvRange>) -> Vec<OsvRange> {
+    // `None` sorts first, which is what an unbounded start should do
+    ranges.sort_by(|a, b| a.start.cmp(&b.start));
+
+    let mut result: Vec<OsvRange> = vec![];
+
+    for range in ranges {
+        match result.last_mut() {
+            // Ends are exclusive, so a range starting at the end of the
+            // previous one is adjacent to it
+            Some(last) if last.end.is_none() || range.start <= last.end => {
+                let extends = match (&last.end, &range.end) {
+                    (Some(last_end), Some(end)) => last_end < end,
+                    (Some(_), None) => true,
+                    (None, _) => false,
+                };
+
+                if extends {
+                    last.end = range.end;
+                    last.last_affected = range.last_affected;
+                }
+            }
+            _ => result.push(range),
+        }
+    }
+
+    result
+}
+
+/// Is the given version affected, given the requirements matching versions
+/// which are not affected (i.e. the `patched` and `unaffected` fields of an
+/// advisory)?
+///
+/// This is consistent with [`affected_ranges`]: a version is affected iff it
+/// lies outside of every unaffected range. Pre-releases are placed on the
+/// version line by semver precedence, so `1.3.0-beta` is unaffected if
+/// `>= 1.2.0` is patched, since advisory authors list the first fixed version
+/// and mean for everything above it to be fixed. Note that this differs from
+/// `VersionReq::matches`, which never matches pre-releases of other versions,
+/// and from [`Versions::is_vulnerable`](crate::advisory::Versions::is_vulnerable),
+/// which matches like Cargo (see [`cargo_matches`](super::cargo_matches)) and
+/// so considers `1.3.0-beta` vulnerable in that case.
+///
+/// Requirements which can't be converted into ranges fall back to
+/// `VersionReq::matches`.
+pub fn is_version_affected(version: &Version, unaffected: &[VersionReq]) -> bool {
+    !unaffected.iter().any(|req| is_unaffected_by(version, req))
+}
+
+/// Is the given version affected, given the `patched` and `unaffected`
+/// fields of an advisory?
+///
+/// This is the same as [`is_version_affected`] on both fields combined. It
+/// stops at the first requirement matching the version, without computing
+/// the affected ranges, so it's cheaper when checking a single version.
+///
+/// Like [`is_version_affected`], this places pre-releases on the version line
+/// by semver precedence, so it can disagree with
+/// [`Versions::is_vulnerable`](crate::advisory::Versions::is_vulnerable) on
+/// pre-releases above a fix.
+pub fn affects(version: &Version, patched: &[VersionReq], unaffected: &[VersionReq]) -> bool {
+    !patched
+        .iter()
+        .chain(unaffected)
+        .any(|req| is_unaffected_by(version, req))
+}
+
+/// Find the lowest patched version to upgrade to from the given version.
+///
+/// Returns `None` if the current version isn't affected. The recommended
+/// version may be in a higher major version than the current one if that's
+/// where the only fix is, so callers should check whether the upgrade is a
+/// breaking change. Below an exclusive bound like `> 1.2.0`, the next
+/// release (`1.2.1`) is recommended.
+pub fn recommend_upgrade(
+    current: &Version,
+    patched: &[VersionReq],
+) -> Result<Option<Version>, RangeError> {
+    let ranges = all_unaffected_ranges(patched, &[])?;
+
+    if ranges.iter().any(|range| range.contains(current)) {
+        return Ok(None);
+    }
+
+    Ok(ranges
+        .iter()
+        .filter_map(|range| {
+            let candidate = match range.start() {
+                Bound::Inclusive(start) => start.clone(),
+                // The release that `increment` yields a pre-release of is the
+                // lowest release above `start`, carrying over past `u64::MAX`
+                Bound::Exclusive(start) => {
+                    let next = increment(start);
+                    Version::new(next.major, next.minor, next.patch)
+                }
+                Bound::Unbounded => return None,
+            };
+            Some(candidate).filter(|v| v > current && range.contains(v))
+        })
+        .next())
+}
+
+/// Is the given pre-release affected, given the requirements matching
+/// versions which are not affected?
+///
+/// Returns `None` if the version isn't a pre-release, and otherwise the same
+/// as [`is_version_affected`], i.e. pre-releases are placed on the version
+/// line by semver precedence. This lets callers single out pre-releases,
+/// whose handling differs from `VersionReq::matches`.
+pub fn is_affected_prerelease(version: &Version, unaffected: &[VersionReq]) -> Option<bool> {
+    if version.is_prerelease() {
+        Some(is_version_affected(version, unaffected))
+    } else {
+        None
+    }
+}
+
+/// Is the given version within the unaffected ranges of the requirement?
+fn is_unaffected_by(version: &Version, req: &VersionReq) -> bool {
+    match ranges_from_version_req(req) {
+        Ok(ranges) => ranges.iter().any(|range| range.contains(version)),
+        Err(_) => req.matches(version),
+    }
+}
+
+/// Get the lowest affected version across the given affected ranges.
+///
+/// Returns `None` if any of the ranges is unbounded below, since then every
+/// version from the very beginning is affected, or if there are no ranges.
+pub fn earliest_affected(ranges: &[OsvRange]) -> Option<Version> {
+    let mut result: Option<&Version> = None;
+
+    for range in ranges {
+        let start = range.start()?;
+
+        if result.map_or(true, |earliest| start < earliest) {
+            result = Some(start);
+        }
+    }
+
+    result.cloned()
+}
+
+/// Get the lowest version from which on nothing is affected by the given
+/// affected ranges, i.e. the minimal safe upgrade target.
+///
+/// With several disjoint ranges, versions between them are unaffected, but
+/// upgrading to them isn't safe in general, so this is the end of the
+/// highest range. Ranges with an inclusive end finish right above their
+/// `last_affected` version (e.g. `1.5.1-0` above `1.5.0`), as no fixed
+/// version is known. Returns `None` if any of the ranges is unbounded above,
+/// since then there is no safe version, or if there are no ranges.
+pub fn earliest_fixed(ranges: &[OsvRange]) -> Option<Version> {
+    let mut result: Option<&Version> = None;
+
+    for range in ranges {
+        let end = range.end()?;
+
+        if result.map_or(true, |fixed| end > fixed) {
+            result = Some(end);
+        }
+    }
+
+    result.cloned()
+}
+
+/// Tighten the boundaries of an affected range to the versions which were
+/// actually published, given as a sorted list.
+///
+/// Both boundaries snap upwards, which leaves the set of published versions
+/// within the range unchanged: the start (or an unbounded start) snaps to the
+/// first published version within the range, and a bounded end to the first
+/// published version at or above it, replacing an inclusive end. An
+/// unbounded end stays that way, since versions still to be published may
+/// be affected. If no published versions lie within the range, it is
+/// returned as-is.
+pub fn snap_to_versions(range: &OsvRange, published: &[Version]) -> OsvRange {
+    let first_at_or_above = |version| published.get(lower_bound(published, version));
+
+    let start = match range.start() {
+        Some(start) => first_at_or_above(start),
+        None => published.first(),
+    };
+    let start = match start {
+        Some(start) if range.contains(start) => start.clone(),
+        _ => return range.clone(),
+    };
+
+    let mut result = range.clone();
+    result.start = Some(without_build(start));
+
+    if let Some(end) = range.end().and_then(first_at_or_above) {
+        result.end = Some(without_build(end.clone()));
+        result.last_affected = None;
+    }
+
+    result
+}
+
+/// Count the published versions within an affected range, given a sorted
+/// list of published versions.
+///
+/// The range includes its start and excludes its end, or extends to the
+/// first or last published version if it's unbounded on that side.
+pub fn count_affected_published(range: &OsvRange, published: &[Version]) -> usize {
+    let start = range
+        .start()
+        .map_or(0, |start| lower_bound(published, start));
+    let end = range
+        .end()
+        .map_or(published.len(), |end| lower_bound(published, end));
+
+    end.saturating_sub(start)
+}
+
+/// Get the index of the first version at or above the given one in a sorted
+/// list of versions
+fn lower_bound(versions: &[Version], version: &Version) -> usize {
+    match versions.binary_search_by(|v| {
+        if v < version {
+            Ordering::Less
+        } else {
+            Ordering::Greater
+        }
+    }) {
+        Ok(i) | Err(i) => i,
+    }
+}
+
+/// Find the index of the range containing the given version in a sorted list
+/// of disjoint ranges, such as the one returned by [`affected_ranges`].
+///
+/// This is a binary search, so it takes logarithmic rather than linear time
+/// in the number of ranges, but the result is unspecified if the ranges
+/// aren't sorted and disjoint.
+pub fn find_containing(ranges: &[OsvRange], version: &Version) -> Option<usize> {
+    ranges
+        .binary_search_by(|range| match range.position(version) {
+            Position::Below => Ordering::Greater,
+            Position::Within => Ordering::Equal,
+            Position::Above => Ordering::Less,
+        })
+        .ok()
+}
+
+/// Whether a version requirement can be converted into ranges, as reported
+/// by [`unsupported_requirements`]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum UnsupportedReason {
+    /// The requirement can be converted
+    Supported,
+
+    /// A range has more predicates than the lower and upper bound it can
+    /// be converted from, e.g. `>= 1.0.0, < 1.5.0, >= 2.0.0`
+    TooManyPredicates,
+
+    /// An exact version (`=x`) is combined with other predicates in the
+    /// same range
+    ExactOp,
+
+    /// The requirement can't be converted for another reason, e.g. it has
+    /// two lower bounds but no upper one
+    Invalid(RangeError),
+}
+
+/// Classify each of the given requirements by whether it can be converted
+/// into ranges, without stopping at the first one which can't.
+///
+/// This is a pre-flight check to audit advisories with; it returns one
+/// classification per requirement, in the same order.
+pub fn unsupported_requirements(reqs: &[VersionReq]) -> Vec<UnsupportedReason> {
+    reqs.iter()
+        .map(|req| match ranges_from_version_req(req) {
+            Ok(_) => UnsupportedReason::Supported,
+            Err(RangeError::Requirement { source, .. }) => match *source {
+                RangeError::TooManyPredicates => UnsupportedReason::TooManyPredicates,
+                RangeError::ExactVersionWithOtherPredicates => UnsupportedReason::ExactOp,
+                other => UnsupportedReason::Invalid(other),
+            },
+            Err(other) => UnsupportedReason::Invalid(other),
+        })
+        .collect()
+}
+
+/// Check the requirements matching unaffected versions of an advisory for
+/// likely authoring mistakes.
+///
+/// Reports requirements which can't be converted into ranges or describe
+/// invalid or empty ranges, as well as any pair of unaffected ranges which
+/// overlap. All problems are returned at once rather than stopping at the
+/// first one.
+pub fn validate_ranges(unaffected: &[VersionReq]) -> Result<(), Vec<RangeError>> {
+    let mut errors = vec![];
+    let mut ranges: Vec<UnaffectedRange> = vec![];
+
+    for req in unaffected {
+        match ranges_from_version_req(req) {
+            Ok(parsed) => {
+                for range in parsed {
+                    if range.is_empty() {
+                        errors.push(RangeError::EmptyRange.in_requirement(req));
+                    } else {
+                        ranges.push(range);
+                    }
+                }
+            }
+            Err(e) => errors.push(e),
+        }
+    }
+
+    for (i, a) in ranges.iter().enumerate() {
+        for b in &ranges[i + 1..] {
+            if a.overlaps(b) {
+                errors.push(RangeError::OverlappingRanges(
+                    Box::new(a.clone()),
+                    Box::new(b.clone()),
+                ));
+            }
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+/// Convert a list of OSV ranges into a `VersionReq` matching the same
+/// versions, by joining the ranges with `||`.
+///
+/// Note that `VersionReq` applies its own rules when matching pre-releases,
+/// so a pre-release contained in one of the ranges isn't necessarily matched
+/// by the resulting requirement.
+pub fn osv_ranges_to_version_req(ranges: &[OsvRange]) -> VersionReq {
+    let req = if ranges.is_empty() {
+        // There is no version below the lowest possible pre-release
+        String::from("< 0.0.0-0")
+    } else {
+        ranges
+            .iter()
+            .map(|range| {
+                let end = match (range.last_affected(), range.end()) {
+                    (Some(last_affected), _) => Some(format!("<= {}", last_affected)),
+                    (None, Some(end)) => Some(format!("< {}", end)),
+                    (None, None) => None,
+                };
+
+                match (range.start(), end) {
+                    (Some(start), Some(end)) => format!(">= {}, {}", start, end),
+                    (Some(start), None) => format!(">= {}", start),
+                    (None, Some(end)) => end,
+                    (None, None) => String::from("*"),
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(" || ")
+    };
+
+    VersionReq::parse(&req).expect("OSV ranges should convert into a valid version requirement")
+}
+
+/// Compute requirements matching the versions which are not affected, given
+/// the ranges of affected versions, e.g. for the `patched` field of an
+/// advisory imported from OSV.
+///
+/// This is the inverse of [`affected_ranges`]: each gap between the affected
+/// ranges becomes a single `>=`/`<` requirement. An affected range starting
+/// at `0.0.0` is treated as unbounded, as that's how OSV denotes ranges
+/// covering all versions below their end.
+pub fn patched_from_osv_ranges(ranges: &[OsvRange]) -> Vec<VersionReq> {
+    let affected = merge_unaffected(ranges.iter().map(affected_as_range).collect());
+    let mut result = vec![];
+
+    // Start of the unaffected range currently being built
+    let mut start = Some(Bound::Unbounded);
+
+    for range in &affected {
+        // Unaffected versions end where the affected range starts...
+        let end = match range.start() {
+            Bound::Unbounded => None,
+            Bound::Inclusive(v) => Some(Bound::Exclusive(v.clone())),
+            Bound::Exclusive(v) => Some(Bound::Inclusive(v.clone())),
+        };
+
+        if let (Some(start), Some(end)) = (start.take(), end) {
+            push_version_req(&mut result, start, end);
+        }
+
+        // ...and start again right after it ends
+        start = match range.end() {
+            Bound::Unbounded => None,
+            Bound::Inclusive(v) => Some(Bound::Exclusive(v.clone())),
+            Bound::Exclusive(v) => Some(Bound::Inclusive(v.clone())),
+        };
+    }
+
+    if let Some(start) = start {
+        push_version_req(&mut result, start, Bound::Unbounded);
+    }
+
+    result
+}
+
+/// Convert an affected range into an [`UnaffectedRange`] covering the same
+/// versions, so it can be merged and inverted like an unaffected one
+fn affected_as_range(range: &OsvRange) -> UnaffectedRange {
+    let start = match range.start() {
+        Some(start) if start != &osv_introduced_floor() => Bound::Inclusive(start.clone()),
+        _ => Bound::Unbounded,
+    };
+
+    let end = match (range.last_affected(), range.end()) {
+        (Some(last_affected), _) => Bound::Inclusive(last_affected.clone()),
+        (None, Some(end)) => Bound::Exclusive(end.clone()),
+        (None, None) => Bound::Unbounded,
+    };
+
+    UnaffectedRange::new(start, end).expect("OSV range should be valid")
+}
+
+/// Add a requirement matching the range between the given bounds to the
+/// given list, unless the range is empty
+fn push_version_req(reqs: &mut Vec<VersionReq>, start: Bound, end: Bound) {
+    if let Ok(range) = UnaffectedRange::new(start, end) {
+        if !range.is_empty() {
+            let req = VersionReq::parse(&range.to_string())
+                .expect("unaffected range should convert into a valid version requirement");
+            reqs.push(req);
+        }
+    }
+}
+
+/// Merge overlapping and adjacent unaffected ranges into the minimal
+/// equivalent set of ranges, sorted by start bound
+fn merge_unaffected(mut ranges: Vec<UnaffectedRange>) -> Vec<UnaffectedRange> {
+    // Empty ranges (e.g. `>= 1.0.0, < 1.0.0`) don't contain any versions,
+    // but would throw off the complement if kept
+    ranges.retain(|range| !range.is_empty());
+
+    // Patched ranges sort first among those with the same start, so merged
+    // ranges starting there are considered patched
+    ranges.sort_by(|a, b| {
+        StartBound(a.start())
+            .cmp(&StartBound(b.start()))
+            .then_with(|| is_unaffected(a).cmp(&is_unaffected(b)))
+            .then_with(|| EndBound(a.end()).cmp(&EndBound(b.end())))
+    });
+
+    // Sorting puts identical ranges next to each other, so duplicates (e.g.
+    // a requirement listed twice) can be dropped up front
+    ranges.dedup();
+
+    let mut result: Vec<UnaffectedRange> = vec![];
+
+    for range in ranges {
+        let merged = match result.last_mut() {
+            Some(last) => last.merge_with(&range),
+            None => false,
+        };
+
+        if !merged {
+            result.push(range);
+        }
+    }
+
+    result
+}
+
+/// Are the versions in the given range not affected because they never were?
+fn is_unaffected(range: &UnaffectedRange) -> bool {
+    range.origin() == RangeOrigin::Unaffected
+}
+
+/// Compute the ranges of versions which are not covered by the given sorted,
+/// non-overlapping unaffected ranges, such as those returned by
+/// [`all_unaffected_ranges`].
+///
+/// The result consists of the gaps between the unaffected ranges, plus the
+/// versions below the first and above the last one, if those are bounded.
+/// Inclusive unaffected bounds turn into exclusive affected ones and vice
+/// versa. An empty input means every version is affected.
+pub fn complement(ranges: &[UnaffectedRange]) -> Vec<OsvRange> {
+    AffectedRanges::new(ranges).collect()
+}
+
+/// Iterator over the ranges of versions which are not covered by the given
+/// sorted, non-overlapping unaffected ranges, yielding the same ranges in
+/// the same order as [`complement`] without collecting them.
+#[derive(Clone, Debug)]
+pub struct AffectedRanges<'a> {
+    /// Unaffected ranges which haven't been visited yet
+    unaffected: std::slice::Iter<'a, UnaffectedRange>,
+
+    /// Start of the next affected range (`None` is unbounded)
+    start: Option<Version>,
+
+    /// Whether the last unaffected range was unbounded above, or the final
+    /// affected range has been yielded
+    done: bool,
+}
+
+impl<'a> AffectedRanges<'a> {
+    /// Iterate over the ranges of versions not covered by the given
+    /// unaffected ranges, which must be sorted and non-overlapping, such as
+    /// those returned by [`all_unaffected_ranges`]
+    pub fn new(unaffected: &'a [UnaffectedRange]) -> Self {
+        AffectedRanges {
+            unaffected: unaffected.iter(),
+            start: None,
+            done: false,
+        }
+    }
+}
+
+impl Iterator for AffectedRanges<'_> {
+    type Item = OsvRange;
+
+    fn next(&mut self) -> Option<OsvRange> {
+        while !self.done {
+            let range = match self.unaffected.next() {
+                Some(range) => range,
+                None => {
+                    // Everything above the last unaffected range is affected
+                    self.done = true;
+                    return Some(OsvRange {
+                        start: self.start.take(),
+                        end: None,
+                        last_affected: None,
+                    });
+                }
+            };
+
+            // Affected versions end where the unaffected range starts. If it
+            // doesn't include its start, that version is the last affected one.
+            let end = match range.start() {
+                Bound::Unbounded => None,
+                Bound::Inclusive(v) => Some((v.clone(), None)),
+                Bound::Exclusive(v) => Some((increment(v), Some(v.clone()))),
+            };
+
+            // ...and start again right after it ends
+            let next_start = match range.end() {
+                Bound::Unbounded => {
+                    self.done = true;
+                    None
+                }
+                Bound::Inclusive(v) => Some(increment(v)),
+                Bound::Exclusive(v) => Some(v.clone()),
+            };
+            let start = std::mem::replace(&mut self.start, next_start);
+
+            if let Some((end, last_affected)) = end {
+                let nonempty = start.as_ref().map_or(true, |start| start < &end);
+
+                if nonempty {
+                    return Some(OsvRange {
+                        start,
+                        end: Some(end),
+                        last_affected,
+                    });
+                }
+            }
+        }
+
+        None
+    }
+}
+
+/// Iterator over the OSV events describing the versions affected given a
+/// requirement matching the unaffected ones, as in [`OsvRange::to_osv_events`].
+///
+/// ```
+/// use rustsec::osv::{AffectedEvents, OsvEvent};
+/// use semver::{Version, VersionReq};
+/// use std::convert::TryFrom;
+///
+/// let patched = VersionReq::parse(">= 1.2.0").unwrap();
+/// let events: Vec<_> = AffectedEvents::try_from(&patched).unwrap().collect();
+/// assert_eq!(
+///     events,
+///     vec![
+///         OsvEvent::Introduced(Version::new(0, 0, 0)),
+///         OsvEvent::Fixed(Version::new(1, 2, 0)),
+///     ]
+/// );
+/// ```
+#[derive(Clone, Debug)]
+pub struct AffectedEvents {
+    events: std::vec::IntoIter<OsvEvent>,
+}
+
+impl TryFrom<&VersionReq> for AffectedEvents {
+    type Error = RangeError;
+
+    fn try_from(unaffected: &VersionReq) -> Result<Self, RangeError> {
+        let events: Vec<_> = affected_ranges(std::slice::from_ref(unaffected))?
+            .iter()
+            .flat_map(OsvRange::to_osv_events)
+            .collect();
+
+        Ok(AffectedEvents {
+            events: events.into_iter(),
+        })
+    }
+}
+
+impl Iterator for AffectedEvents {
+    type Item = OsvEvent;
+
+    fn next(&mut self) -> Option<OsvEvent> {
+        self.events.next()
+    }
+}
+
+/// Get the lowest possible version which is greater than the given one.
+///
+/// Exclusive bounds need to be converted into inclusive ones (or vice versa)
+/// when inverting ranges, as OSV ranges always have an inclusive start and
+/// an exclusive end.
+pub(super) fn increment(version: &Version) -> Version {
+    let mut result = version.clone();
+
+    // Build metadata doesn't affect precedence
+    result.build.clear();
+
+    if result.pre.is_empty() {
+        // `1.2.3` -> `1.2.4-0`: the lowest pre-release of the next patch
+        // version is the lowest version above `1.2.3` according to semver 2.0.
+        // If the patch (or minor) version can't go any higher, the next minor
+        // (or major) version comes next instead.
+        if let Some(patch) = result.patch.checked_add(1) {
+            result.patch = patch;
+        } else if let Some(minor) = result.minor.checked_add(1) {
+            result.minor = minor;
+            result.patch = 0;
+        } else if let Some(major) = result.major.checked_add(1) {
+            result.major = major;
+            result.minor = 0;
+            result.patch = 0;
+        } else {
+            // There is no version above the highest possible one
+            return result;
+        }
+    }
+
+    // `1.2.3-alpha` -> `1.2.3-alpha.0`: adding an identifier makes a
+    // pre-release greater, and `0` is the lowest possible identifier
+    result.pre.push(Identifier::Numeric(0));
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::affected_as_range;
+    use super::{
+        affected_interval_count, affected_ranges, affected_ranges_with_explicit_start,
+        affected_ranges_with_metadata, affected_ranges_within, affected_sets_disjoint, affects,
+        all_unaffected_ranges, complement, count_affected_published, earliest_affected,
+        earliest_fixed, find_conflicting_advisories, find_containing, increment,
+        is_affected_prerelease, is_version_affected, merge_affected, merge_unaffected,
+        osv_ranges_to_version_req, patched_from_osv_ranges, recommend_upgrade, snap_to_versions,
+        union_affected, unsupported_requirements, validate_osv_ranges, validate_ranges,
+        AffectedEvents, AffectedRanges, Conflict, OsvRange, RangeError, RangeOrigin,
+        UnsupportedReason,
+    };
+    use crate::osv::unaffected_range::{
+        ranges_from_version_req, Bound, EndBound, StartBound, UnaffectedRange,
+    };
+    use crate::osv::OsvEvent;
+    use proptest::prelude::*;
+    use semver::{Version, VersionReq};
+    use serde_json::json;
+    use std::convert::TryFrom;
+
+    fn reqs(input: &[&str]) -> Vec<VersionReq> {
+        input
+            .iter()
+            .map(|s| VersionReq::parse(s).unwrap())
+            .collect()
+    }
+
+    fn unaffected(input: &[&str]) -> Vec<UnaffectedRange> {
+        reqs(input)
+            .iter()
+            .flat_map(|req| ranges_from_version_req(req).unwrap())
+            .collect()
+    }
+
+    fn range(start: Option<&str>, end: Option<&str>) -> OsvRange {
+        OsvRange {
+            start: start.map(|v| Version::parse(v).unwrap()),
+            end: end.map(|v| Version::parse(v).unwrap()),
+            last_affected: None,
+        }
+    }
+
+    fn range_through(start: Option<&str>, last_affected: &str) -> OsvRange {
+        let last_affected = Version::parse(last_affected).unwrap();
+
+        OsvRange {
+            start: start.map(|v| Version::parse(v).unwrap()),
+            end: Some(increment(&last_affected)),
+            last_affected: Some(last_affected),
+        }
+    }
+
+    fn complement_of(input: &[&str]) -> Vec<OsvRange> {
+        affected_ranges(&reqs(input)).unwrap()
+    }
+
+    #[test]
+    fn single_patched_version() {
+        assert_eq!(
+            affected_ranges(&reqs(&[">= 1.2.0"])).unwrap(),
+            vec![range(None, Some("1.2.0"))]
+        );
+    }
+
+    #[test]
+    fn two_patched_branches() {
+        assert_eq!(
+            affected_ranges(&reqs(&[">= 1.2.0, < 2.0.0", ">= 2.1.0"])).unwrap(),
+            vec![
+                range(None, Some("1.2.0")),
+                range(Some("2.0.0"), Some("2.1.0"))
+            ]
+        );
+    }
+
+    #[test]
+    fn overlapping_unaffected_ranges() {
+        assert_eq!(
+            affected_ranges(&reqs(&[">= 1.2.0, < 2.0.0", ">= 1.5.0, <= 2.5.0"])).unwrap(),
+            vec![range(None, Some("1.2.0")), range(Some("2.5.1-0"), None)]
+        );
+    }
+
+    #[test]
+    fn increment_versions() {
+        assert_eq!(
+            increment(&Version::parse("1.2.3").unwrap()),
+            Version::parse("1.2.4-0").unwrap()
+        );
+        assert_eq!(
+            increment(&Version::parse("1.2.3-alpha").unwrap()),
+            Version::parse("1.2.3-alpha.0").unwrap()
+        );
+
+        // Overflowing components carry over into the next one
+        let max = u64::MAX;
+        assert_eq!(
+            increment(&Version::new(1, 2, max)),
+            Version::parse("1.3.0-0").unwrap()
+        );
+        assert_eq!(
+            increment(&Version::new(1, max, max)),
+            Version::parse("2.0.0-0").unwrap()
+        );
+        assert_eq!(
+            increment(&Version::new(max, max, max)),
+            Version::new(max, max, max)
+        );
+    }
+
+    #[test]
+    fn merge_inclusive_end_with_exclusive_start() {
+        let merged = merge_unaffected(unaffected(&["> 1.0.0", "<= 1.0.0"]));
+        assert_eq!(merged.len(), 1);
+        assert_eq!(merged[0].start(), &Bound::Unbounded);
+        assert_eq!(merged[0].end(), &Bound::Unbounded);
+    }
+
+    #[test]
+    fn merge_exclusive_end_with_inclusive_start() {
+        let merged = merge_unaffected(unaffected(&["< 1.0.0", ">= 1.0.0, < 2.0.0"]));
+        assert_eq!(merged.len(), 1);
+        assert_eq!(merged[0].start(), &Bound::Unbounded);
+        assert_eq!(merged[0].end(), &Bound::Exclusive(Version::new(2, 0, 0)));
+    }
+
+    #[test]
+    fn no_merge_with_gap() {
+        let merged = merge_unaffected(unaffected(&["> 1.0.0", "< 1.0.0"]));
+        assert_eq!(merged, unaffected(&["< 1.0.0", "> 1.0.0"]));
+        assert_eq!(
+            complement_of(&["> 1.0.0", "< 1.0.0"]),
+            vec![range_through(Some("1.0.0"), "1.0.0")]
+        );
+    }
+
+    #[test]
+    fn merge_nested() {
+        let merged = merge_unaffected(unaffected(&[">= 1.0.0, < 3.0.0", ">= 1.5.0, < 2.0.0"]));
+        assert_eq!(merged, unaffected(&[">= 1.0.0, < 3.0.0"]));
+    }
+
+    #[test]
+    fn prerelease_patched_version() {
+        let beta = Version::parse("1.0.0-beta").unwrap();
+
+        let affected = complement_of(&[">= 1.0.0-alpha"]);
+        assert_eq!(affected, vec![range(None, Some("1.0.0-alpha"))]);
+        assert!(!affected[0].contains(&beta));
+
+        let affected = complement_of(&[">= 1.0.0"]);
+        assert_eq!(affected, vec![range(None, Some("1.0.0"))]);
+        assert!(affected[0].contains(&beta));
+    }
+
+    #[test]
+    fn version_req_round_trip() {
+        let samples: Vec<Version> = (0..4)
+            .flat_map(|major| {
+                (0..4).flat_map(move |minor| (0..4).map(move |patch| (major, minor, patch)))
+            })
+            .map(|(major, minor, patch)| Version::new(major, minor, patch))
+            .collect();
+
+        for input in &[
+            &[">= 1.2.0"][..],
+            &["< 1.0.0", ">= 1.2.0, < 2.0.0", ">= 2.1.0"],
+            &["^0.1.2", "~1.2", "> 3.1.0"],
+            &["*"],
+            &[],
+        ] {
+            let ranges = affected_ranges(&reqs(input)).unwrap();
+            let req = osv_ranges_to_version_req(&ranges);
+
+            for version in &samples {
+                assert_eq!(
+                    ranges.iter().any(|range| range.contains(version)),
+                    req.matches(version),
+                    "{} in {:?} vs {}",
+                    version,
+                    input,
+                    req
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn wildcard_unaffected() {
+        assert_eq!(complement_of(&["*"]), vec![]);
+        assert_eq!(complement_of(&[">= 1.2.0", "*"]), vec![]);
+    }
+
+    #[test]
+    fn gaps_between_two_patched_branches() {
+        assert_eq!(
+            complement_of(&[">= 1.2.0, < 2.0.0", ">= 2.3.0"]),
+            vec![
+                range(None, Some("1.2.0")),
+                range(Some("2.0.0"), Some("2.3.0"))
+            ]
+        );
+    }
+
+    #[test]
+    fn gaps_between_three_patched_branches() {
+        assert_eq!(
+            complement_of(&[">= 3.1.0", ">= 2.3.0, < 3.0.0", ">= 1.2.0, < 2.0.0"]),
+            vec![
+                range(None, Some("1.2.0")),
+                range(Some("2.0.0"), Some("2.3.0")),
+                range(Some("3.0.0"), Some("3.1.0"))
+            ]
+        );
+    }
+
+    #[test]
+    fn gaps_with_flipped_bounds() {
+        // An exclusive unaffected start leaves its own version affected, while
+        // an inclusive unaffected end means the next affected range starts at
+        // the lowest version above it
+        assert_eq!(
+            complement_of(&["> 1.2.0, <= 1.9.0"]),
+            vec![range_through(None, "1.2.0"), range(Some("1.9.1-0"), None)]
+        );
+    }
+
+    #[test]
+    fn validate_consistent_ranges() {
+        assert_eq!(
+            validate_ranges(&reqs(&["^1.2.3", ">= 2.0.0", "< 0.5.0"])),
+            Ok(())
+        );
+        assert_eq!(validate_ranges(&[]), Ok(()));
+    }
+
+    #[test]
+    fn validate_contradictory_ranges() {
+        let input = reqs(&[
+            ">= 1.2.0",
+            "^1.1.5",
+            ">= 2.0.0, < 1.0.0",
+            "> 3.0.0, < 3.0.0",
+        ]);
+        let errors = validate_ranges(&input).unwrap_err();
+
+        assert_eq!(
+            errors,
+            vec![
+                RangeError::InvalidRange.in_requirement(&input[2]),
+                RangeError::EmptyRange.in_requirement(&input[3]),
+                RangeError::OverlappingRanges(
+                    Box::new(unaffected(&[">= 1.2.0"]).remove(0)),
+                    Box::new(unaffected(&["^1.1.5"]).remove(0)),
+                ),
+            ]
+        );
+        assert_eq!(
+            errors[2].to_string(),
+            "unaffected ranges >=1.2.0 and >=1.1.5, <2.0.0 overlap"
+        );
+    }
+
+    #[test]
+    fn osv_events_for_boundary_flavors() {
+        let events = |input| {
+            let events: Vec<_> = complement_of(&[input])
+                .iter()
+                .map(OsvRange::to_osv_events)
+                .collect();
+            serde_json::to_value(events).unwrap()
+        };
+
+        assert_eq!(
+            events(">= 1.2.0"),
+            json!([[{"introduced": "0.0.0"}, {"fixed": "1.2.0"}]])
+        );
+        assert_eq!(
+            events("> 1.2.0"),
+            json!([[{"introduced": "0.0.0"}, {"last_affected": "1.2.0"}]])
+        );
+
+        // Mixed bounds flip both ways: the affected range below ends with the
+        // exclusive unaffected start, and the one above starts right after
+        // the inclusive unaffected end
+        assert_eq!(
+            events("> 1.0.0, <= 1.2.0"),
+            json!([
+                [{"introduced": "0.0.0"}, {"last_affected": "1.0.0"}],
+                [{"introduced": "1.2.1-0"}]
+            ])
+        );
+
+        let unaffected = reqs(&["> 1.0.0, <= 1.2.0"]);
+        for (version, affected) in &[
+            ("1.0.0", true),
+            ("1.0.1-0", false),
+            ("1.2.0", false),
+            ("1.2.1-0", true),
+        ] {
+            let version = Version::parse(version).unwrap();
+            assert_eq!(is_version_affected(&version, &unaffected), *affected);
+        }
+    }
+
+    /// Straightforward quadratic merge, used as a reference implementation
+    fn merge_pairwise(mut ranges: Vec<UnaffectedRange>) -> Vec<UnaffectedRange> {
+        let mut merged = true;
+
+        while merged {
+            merged = false;
+
+            'outer: for i in 0..ranges.len() {
+                for j in 0..ranges.len() {
+                    let (a, b) = (&ranges[i], &ranges[j]);
+
+                    if i != j && (a.overlaps(b) || a.is_adjacent(b)) {
+                        let start = std::cmp::min(StartBound(a.start()), StartBound(b.start()));
+                        let end = std::cmp::max(EndBound(a.end()), EndBound(b.end()));
+                        let union = UnaffectedRange::new(start.0.clone(), end.0.clone()).unwrap();
+
+                        ranges[i] = union;
+                        ranges.remove(j);
+                        merged = true;
+                        break 'outer;
+                    }
+                }
+            }
+        }
+
+        ranges.sort_by(|a, b| StartBound(a.start()).cmp(&StartBound(b.start())));
+        ranges
+    }
+
+    #[test]
+    fn merge_matches_pairwise_merge() {
+        // xorshift, to get reproducible pseudo-random input without a
+        // dependency on a random number generator
+        let mut state = 0x2545_f491_4f6c_dd1d_u64;
+        let mut next = move |n: u64| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state % n
+        };
+
+        let bound = |next: &mut dyn FnMut(u64) -> u64| {
+            let version = Version::new(next(3), next(3), next(2));
+            match next(5) {
+                0 => Bound::Unbounded,
+                1 | 2 => Bound::Inclusive(version),
+                _ => Bound::Exclusive(version),
+            }
+        };
+
+        for _ in 0..500 {
+            let mut ranges = vec![];
+
+            for _ in 0..next(8) {
+                let start = bound(&mut next);
+                let end = bound(&mut next);
+
+                if let Ok(range) = UnaffectedRange::new(start, end) {
+                    if !range.is_empty() {
+                        ranges.push(range);
+                    }
+                }
+            }
+
+            assert_eq!(
+                merge_unaffected(ranges.clone()),
+                merge_pairwise(ranges.clone()),
+                "{:?}",
+                ranges
+            );
+        }
+    }
+
+    #[test]
+    fn version_affected() {
+        let unaffected = reqs(&["^1.2.3", ">= 2.1.0", "< 0.5.0"]);
+        let affected = |v| is_version_affected(&Version::parse(v).unwrap(), &unaffected);
+
+        assert!(!affected("1.2.3"));
+        assert!(affected("1.2.2"));
+        assert!(!affected("2.1.0"));
+        assert!(affected("2.0.9"));
+        assert!(affected("2.1.0-rc.1"));
+        assert!(!affected("0.4.9"));
+        assert!(affected("0.5.0"));
+
+        // Pre-releases above a patched version are considered patched
+        assert!(!affected("3.0.0-alpha"));
+
+        assert!(affected("1.0.0") && is_version_affected(&Version::new(1, 0, 0), &[]));
+    }
+
+    #[test]
+    fn version_affected_matches_affected_ranges() {
+        for input in &[&[">= 1.2.0"][..], &["> 1.0.0, <= 1.2.0", "~0.3"], &["*"]] {
+            let ranges = complement_of(input);
+
+            for version in &[
+                "0.0.0", "0.3.5", "1.0.0", "1.0.1-0", "1.2.0", "1.2.1", "9.0.0",
+            ] {
+                let version = Version::parse(version).unwrap();
+                assert_eq!(
+                    is_version_affected(&version, &reqs(input)),
+                    ranges.iter().any(|range| range.contains(&version)),
+                    "{} in {:?}",
+                    version,
+                    input
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn patched_from_affected_ranges() {
+        assert_eq!(
+            patched_from_osv_ranges(&[
+                range(None, Some("1.2.0")),
+                range(Some("2.0.0"), Some("2.1.0"))
+            ]),
+            reqs(&[">= 1.2.0, < 2.0.0", ">= 2.1.0"])
+        );
+        assert_eq!(
+            patched_from_osv_ranges(&[range(Some("0.0.0"), Some("1.0.0"))]),
+            reqs(&[">= 1.0.0"])
+        );
+        assert_eq!(
+            patched_from_osv_ranges(&[range_through(Some("0.5.0"), "1.0.0")]),
+            reqs(&["< 0.5.0", "> 1.0.0"])
+        );
+        assert_eq!(
+            patched_from_osv_ranges(&[
+                range(Some("1.0.0"), Some("1.5.0")),
+                range(Some("1.2.0"), None)
+            ]),
+            reqs(&["< 1.0.0"])
+        );
+        assert_eq!(patched_from_osv_ranges(&[range(None, None)]), reqs(&[]));
+        assert_eq!(patched_from_osv_ranges(&[]), reqs(&["*"]));
+    }
+
+    #[test]
+    fn patched_round_trip() {
+        for input in &[
+            &[">= 1.2.0"][..],
+            &[">= 1.2.0, < 2.0.0", ">= 2.1.0"],
+            &["< 1.0.0", "> 1.5.0"],
+            &["*"],
+        ] {
+            let patched = patched_from_osv_ranges(&complement_of(input));
+            assert_eq!(patched, reqs(input), "{:?}", input);
+        }
+
+        // Inclusive unaffected ends turn into an affected range starting at
+        // the lowest version above them, so only the first conversion isn't
+        // exact, but it still matches the same versions
+        let input = &["< 1.0.0", "> 1.5.0, <= 1.9.0", "= 3.0.0"];
+        let patched = patched_from_osv_ranges(&complement_of(input));
+        assert_eq!(
+            patched,
+            reqs(&["< 1.0.0", "> 1.5.0, < 1.9.1-0", ">= 3.0.0, < 3.0.1-0"])
+        );
+        assert_eq!(affected_ranges(&patched).unwrap(), complement_of(input));
+        assert_eq!(
+            patched_from_osv_ranges(&affected_ranges(&patched).unwrap()),
+            patched
+        );
+    }
+
+    #[test]
+    fn yanked_fixed_version() {
+        let yanked = [Version::new(1, 2, 0), Version::new(3, 0, 0)];
+
+        assert_eq!(
+            affected_ranges_with_metadata(&reqs(&[">= 1.2.0, < 2.0.0", ">= 2.1.0"]), &yanked)
+                .unwrap(),
+            vec![
+                (range(None, Some("1.2.0")), true),
+                (range(Some("2.0.0"), Some("2.1.0")), false)
+            ]
+        );
+        assert_eq!(
+            affected_ranges_with_metadata(&reqs(&["< 1.0.0", "> 1.2.0"]), &yanked).unwrap(),
+            vec![(range_through(Some("1.0.0"), "1.2.0"), false)]
+        );
+        assert_eq!(
+            affected_ranges_with_metadata(&reqs(&["< 3.0.0"]), &yanked).unwrap(),
+            vec![(range(Some("3.0.0"), None), false)]
+        );
+    }
+
+    #[test]
+    fn combine_patched_and_unaffected() {
+        let never_affected = |input| {
+            let mut ranges = unaffected(&[input]);
+            ranges.remove(0).with_origin(RangeOrigin::Unaffected)
+        };
+
+        assert_eq!(
+            all_unaffected_ranges(&reqs(&[">= 1.2"]), &reqs(&["< 0.5"])).unwrap(),
+            vec![
+                never_affected("< 0.5.0"),
+                unaffected(&[">= 1.2.0"]).remove(0)
+            ]
+        );
+
+        // Overlapping and duplicate ranges are merged across both fields
+        assert_eq!(
+            all_unaffected_ranges(&reqs(&[">= 1.2", "< 0.5"]), &reqs(&["^1.3", "< 0.5"])).unwrap(),
+            unaffected(&["< 0.5.0", ">= 1.2.0"])
+        );
+        assert_eq!(
+            all_unaffected_ranges(&reqs(&[">= 1.2"]), &reqs(&["< 1.2"])).unwrap(),
+            vec![never_affected("*")]
+        );
+    }
+
+    #[test]
+    fn range_origins_in_osv_events() {
+        let patched = reqs(&["^1.2.0"]);
+        let never_affected = reqs(&["< 0.5.0", ">= 3.0.0", ">= 2.5.1-0, < 2.6.0"]);
+        let ranges = complement(&all_unaffected_ranges(&patched, &never_affected).unwrap());
+        let events: Vec<_> = ranges.iter().map(OsvRange::to_osv_events).collect();
+
+        // OSV has no event for where versions which were never affected
+        // start, and most versions have no predecessor to close the range
+        // with `last_affected` on, so they end affected ranges like fixes do
+        assert_eq!(
+            serde_json::to_value(events).unwrap(),
+            json!([
+                [{"introduced": "0.5.0"}, {"fixed": "1.2.0"}],
+                [{"introduced": "2.0.0"}, {"fixed": "2.5.1-0"}],
+                [{"introduced": "2.6.0"}, {"fixed": "3.0.0"}]
+            ])
+        );
+
+        let all_patched = [patched, never_affected].concat();
+        assert_eq!(ranges, affected_ranges(&all_patched).unwrap());
+    }
+
+    #[test]
+    fn error_contains_requirement() {
+        let err = affected_ranges(&reqs(&[">= 1.2.0", ">= 1.0, >= 2.0"])).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "multiple lower bounds in \">=1.0.0, >=2.0.0\""
+        );
+
+        let err = affected_ranges(&reqs(&["< 1.0.0 || >= 3.0.0, < 2.0.0"])).unwrap_err();
+        assert_eq!(
+            err,
+            RangeError::InvalidRange.in_requirement(&reqs(&["< 1.0.0 || >= 3.0.0, < 2.0.0"])[0])
+        );
+        assert!(err.to_string().contains(">=3.0.0, <2.0.0"));
+    }
+
+    #[test]
+    fn disjunctions() {
+        assert_eq!(
+            complement_of(&[">=1.0, <1.5 || >=2.0"]),
+            complement_of(&[">=1.0, <1.5", ">=2.0"])
+        );
+        assert_eq!(
+            complement_of(&[">=1.0, <1.5 || >=2.0"]),
+            vec![
+                range(None, Some("1.0.0")),
+                range(Some("1.5.0"), Some("2.0.0"))
+            ]
+        );
+
+        // Disjuncts are merged with each other and the other requirements
+        assert_eq!(
+            complement_of(&["< 1.0.0 || ^1.2", ">= 1.0.0, < 1.2.0"]),
+            vec![range(Some("2.0.0"), None)]
+        );
+    }
+
+    #[test]
+    fn complement_of_nothing() {
+        assert_eq!(complement(&[]), vec![OsvRange::unbounded()]);
+    }
+
+    #[test]
+    fn no_patched_versions() {
+        let affected = affected_ranges(&[]).unwrap();
+
+        assert_eq!(affected, vec![OsvRange::unbounded()]);
+        assert_eq!(
+            affected[0].to_osv_events(),
+            vec![OsvEvent::Introduced(Version::new(0, 0, 0))]
+        );
+    }
+
+    #[test]
+    fn complement_of_unbounded_start() {
+        assert_eq!(
+            complement(&unaffected(&["< 1.0.0"])),
+            vec![range(Some("1.0.0"), None)]
+        );
+        assert_eq!(
+            complement(&unaffected(&["<= 1.0.0"])),
+            vec![range(Some("1.0.1-0"), None)]
+        );
+    }
+
+    #[test]
+    fn complement_of_unbounded_end() {
+        assert_eq!(
+            complement(&unaffected(&[">= 1.0.0"])),
+            vec![range(None, Some("1.0.0"))]
+        );
+        assert_eq!(
+            complement(&unaffected(&["> 1.0.0"])),
+            vec![range_through(None, "1.0.0")]
+        );
+    }
+
+    #[test]
+    fn union_of_overlapping_advisories() {
+        let advisories = vec![
+            reqs(&["< 1.0.0", ">= 1.5.0"]),
+            reqs(&["< 1.2.0", ">= 2.0.0"]),
+        ];
+
+        assert_eq!(
+            union_affected(&advisories).unwrap(),
+            vec![range(Some("1.0.0"), Some("2.0.0"))]
+        );
+    }
+
+    #[test]
+    fn union_keeps_furthest_end() {
+        let advisories = vec![
+            reqs(&["< 1.0.0", "> 1.5.0"]),
+            reqs(&["< 1.2.0", ">= 1.4.0"]),
+            reqs(&["< 3.0.0", ">= 3.1.0"]),
+        ];
+
+        assert_eq!(
+            union_affected(&advisories).unwrap(),
+            vec![
+                range_through(Some("1.0.0"), "1.5.0"),
+                range(Some("3.0.0"), Some("3.1.0")),
+            ]
+        );
+    }
+
+    #[test]
+    fn merge_adjacent_and_unbounded_affected_ranges() {
+        assert_eq!(
+            merge_affected(vec![
+                range(Some("2.0.0"), None),
+                range(Some("1.0.0"), Some("2.0.0")),
+                range(None, Some("0.5.0")),
+                range(Some("0.1.0"), Some("0.2.0")),
+            ]),
+            vec![range(None, Some("0.5.0")), range(Some("1.0.0"), None)]
+        );
+        assert_eq!(merge_affected(vec![]), vec![]);
+    }
+
+    #[test]
+    fn find_containing_range() {
+        let ranges = complement_of(&[">= 1.0.0, < 1.2.0", ">= 2.0.0, < 2.5.0", "> 3.0.0"]);
+        assert_eq!(ranges.len(), 3);
+
+        let find = |v: &str| find_containing(&ranges, &Version::parse(v).unwrap());
+
+        assert_eq!(find("0.1.0"), Some(0));
+        assert_eq!(find("1.0.0-rc.1"), Some(0));
+        assert_eq!(find("1.0.0"), None);
+        assert_eq!(find("1.1.9"), None);
+        assert_eq!(find("1.2.0"), Some(1));
+        assert_eq!(find("1.9.9"), Some(1));
+        assert_eq!(find("2.0.0"), None);
+        assert_eq!(find("2.5.0"), Some(2));
+        assert_eq!(find("3.0.0"), Some(2));
+        assert_eq!(find("3.0.1"), None);
+        assert_eq!(find_containing(&[], &Version::new(1, 0, 0)), None);
+
+        // Agrees with a linear scan
+        for v in &[
+            "0.0.0", "1.1.0", "1.5.0", "2.2.0", "2.7.0", "3.0.0", "4.0.0",
+        ] {
+            let v = Version::parse(v).unwrap();
+            assert_eq!(
+                find_containing(&ranges, &v),
+                ranges.iter().position(|range| range.contains(&v))
+            );
+        }
+    }
+
+    #[test]
+    fn classify_unsupported_requirements() {
+        assert_eq!(
+            unsupported_requirements(&reqs(&[
+                ">= 1.2.0",
+                ">= 1.0.0, < 1.5.0, >= 2.0.0",
+                "= 1.1.0",
+                "= 1.1.0, < 2.0.0",
+                ">= 1.0.0, >= 2.0.0",
+            ])),
+            vec![
+                UnsupportedReason::Supported,
+                UnsupportedReason::TooManyPredicates,
+                UnsupportedReason::Supported,
+                UnsupportedReason::ExactOp,
+                UnsupportedReason::Invalid(RangeError::MultipleLowerBounds),
+            ]
+        );
+        assert_eq!(unsupported_requirements(&[]), vec![]);
+    }
+
+    #[test]
+    fn earliest_affected_version() {
+        let bounded = vec![
+            range(Some("2.0.0"), Some("2.1.0")),
+            range(Some("1.0.0-beta"), Some("1.2.0")),
+            range(Some("3.0.0"), None),
+        ];
+        assert_eq!(
+            earliest_affected(&bounded),
+            Some(Version::parse("1.0.0-beta").unwrap())
+        );
+
+        let unbounded_below = vec![
+            range(Some("2.0.0"), Some("2.1.0")),
+            range(None, Some("1.2.0")),
+        ];
+        assert_eq!(earliest_affected(&unbounded_below), None);
+        assert_eq!(earliest_affected(&complement_of(&[">= 1.0.0"])), None);
+
+        assert_eq!(earliest_affected(&[]), None);
+    }
+
+    #[test]
+    fn earliest_fixed_version() {
+        let single = complement_of(&[">= 1.2.0"]);
+        assert_eq!(earliest_fixed(&single), Some(Version::new(1, 2, 0)));
+
+        // Versions between the ranges aren't a safe upgrade target
+        let multiple = complement_of(&["^1.2.0", ">= 2.1.0", "< 0.5.0"]);
+        assert_eq!(multiple.len(), 2);
+        assert_eq!(earliest_fixed(&multiple), Some(Version::new(2, 1, 0)));
+
+        let last_affected = complement_of(&["> 1.5.0"]);
+        assert_eq!(
+            earliest_fixed(&last_affected),
+            Some(Version::parse("1.5.1-0").unwrap())
+        );
+
+        let unfixed = complement_of(&["< 1.0.0", ">= 1.2.0, < 2.0.0"]);
+        assert_eq!(earliest_fixed(&unfixed), None);
+
+        assert_eq!(earliest_fixed(&[]), None);
+    }
+
+    #[test]
+    fn duplicate_requirements() {
+        let deduplicated = reqs(&[">= 1.2.0, < 1.3.0", ">= 2.0.0", "< 0.5.0"]);
+        let duplicated = reqs(&[
+            ">= 2.0.0",
+            ">= 1.2.0, < 1.3.0",
+            "< 0.5.0",
+            ">= 1.2.0, < 1.3.0",
+            ">= 2.0.0",
+        ]);
+
+        assert_eq!(
+            affected_ranges(&duplicated).unwrap(),
+            affected_ranges(&deduplicated).unwrap()
+        );
+        assert_eq!(
+            merge_unaffected(unaffected(&["< 0.5.0", ">= 1.0.0", "< 0.5.0"])),
+            unaffected(&["< 0.5.0", ">= 1.0.0"])
+        );
+
+        // The same requirement in both fields is considered patched
+        let both = all_unaffected_ranges(&reqs(&[">= 1.2.0"]), &reqs(&[">= 1.2.0"])).unwrap();
+        assert_eq!(both, unaffected(&[">= 1.2.0"]));
+        assert_eq!(both[0].origin(), RangeOrigin::Patched);
+    }
+
+    #[test]
+    fn affects_matches_affected_ranges() {
+        let patched = reqs(&[">= 1.2.5, < 1.3.0", ">= 1.3.2"]);
+        let unaffected = reqs(&["< 1.0.0", "= 1.1.0"]);
+        let ranges = complement(&all_unaffected_ranges(&patched, &unaffected).unwrap());
+
+        for v in &[
+            "0.9.0",
+            "1.0.0",
+            "1.1.0",
+            "1.1.1",
+            "1.2.5-rc.1",
+            "1.2.5",
+            "1.3.0",
+            "1.3.2",
+            "2.0.0",
+        ] {
+            let v = Version::parse(v).unwrap();
+            assert_eq!(
+                affects(&v, &patched, &unaffected),
+                ranges.iter().any(|range| range.contains(&v)),
+                "{}",
+                v
+            );
+        }
+
+        assert!(affects(&Version::new(1, 0, 0), &patched, &unaffected));
+        assert!(!affects(&Version::new(1, 1, 0), &patched, &unaffected));
+        assert!(affects(&Version::new(1, 0, 0), &[], &[]));
+    }
+
+    #[test]
+    fn unaffected_range_collapsed_to_a_point() {
+        let affected = complement_of(&[">= 1.0.0, <= 1.0.0"]);
+
+        assert_eq!(
+            affected,
+            vec![range(None, Some("1.0.0")), range(Some("1.0.1-0"), None)]
+        );
+        assert_eq!(affected, complement_of(&["= 1.0.0"]));
+
+        let events: Vec<_> = affected.iter().flat_map(OsvRange::to_osv_events).collect();
+        assert_eq!(
+            events,
+            vec![
+                OsvEvent::Introduced(Version::new(0, 0, 0)),
+                OsvEvent::Fixed(Version::new(1, 0, 0)),
+                OsvEvent::Introduced(Version::parse("1.0.1-0").unwrap()),
+            ]
+        );
+
+        let v = |s: &str| Version::parse(s).unwrap();
+        let unaffected = reqs(&[">= 1.0.0, <= 1.0.0"]);
+        assert!(is_version_affected(&v("1.0.0-rc.1"), &unaffected));
+        assert!(!is_version_affected(&v("1.0.0"), &unaffected));
+        assert!(is_version_affected(&v("1.0.1-alpha"), &unaffected));
+        assert!(is_version_affected(&v("1.0.1"), &unaffected));
+    }
+
+    #[test]
+    fn explicit_start() {
+        let unaffected = reqs(&[">= 1.2.0", "< 0.1.0"]);
+
+        let explicit = affected_ranges_with_explicit_start(&reqs(&[">= 1.2.0"])).unwrap();
+        assert_eq!(explicit, vec![range(Some("0.0.0"), Some("1.2.0"))]);
+        #[cfg(feature = "osv")]
+        assert_eq!(
+            serde_json::to_value(&explicit[0]).unwrap(),
+            json!({"introduced": "0.0.0", "fixed": "1.2.0"})
+        );
+
+        // Ranges used for computations keep the unbounded start
+        assert_eq!(
+            affected_ranges(&reqs(&[">= 1.2.0"])).unwrap(),
+            vec![range(None, Some("1.2.0"))]
+        );
+
+        assert_eq!(
+            affected_ranges_with_explicit_start(&unaffected).unwrap(),
+            affected_ranges(&unaffected).unwrap()
+        );
+    }
+
+    #[test]
+    fn contains_matches_bounds() {
+        let versions: Vec<_> = [
+            "0.0.0-0",
+            "0.0.0",
+            "0.9.0",
+            "1.0.0-rc.1",
+            "1.0.0",
+            "1.2.0",
+            "1.2.1-0",
+            "1.2.1",
+            "2.0.0-alpha",
+            "2.0.0",
+            "3.0.0",
+        ]
+        .iter()
+        .map(|v| Version::parse(v).unwrap())
+        .collect();
+
+        let ranges = vec![
+            OsvRange::unbounded(),
+            range(None, Some("1.0.0")),
+            range(Some("1.0.0"), None),
+            range(Some("1.0.0-rc.1"), Some("2.0.0")),
+            range_through(Some("1.0.0"), "1.2.0"),
+            range_through(None, "0.0.0"),
+        ];
+
+        // `UnaffectedRange` matches on the kinds of bounds instead, which makes
+        // for an independent reference
+        for range in &ranges {
+            let reference = affected_as_range(range);
+
+            for version in &versions {
+                assert_eq!(
+                    range.contains(version),
+                    reference.contains(version),
+                    "{} in {}",
+                    version,
+                    range
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn affected_within_universe() {
+        let unaffected = reqs(&[">= 1.2.0, < 2.0.0", ">= 2.1.0, < 3.0.0", ">= 4.0.0"]);
+
+        // The universe trims the ranges at both ends, and drops ranges
+        // outside of it
+        assert_eq!(
+            affected_ranges_within(&unaffected, &range(Some("0.3.0"), Some("2.5.0"))).unwrap(),
+            vec![
+                range(Some("0.3.0"), Some("1.2.0")),
+                range(Some("2.0.0"), Some("2.1.0")),
+            ]
+        );
+        assert_eq!(
+            affected_ranges_within(&unaffected, &range(Some("0.3.0"), None)).unwrap(),
+            vec![
+                range(Some("0.3.0"), Some("1.2.0")),
+                range(Some("2.0.0"), Some("2.1.0")),
+                range(Some("3.0.0"), Some("4.0.0")),
+            ]
+        );
+        assert_eq!(
+            affected_ranges_within(&unaffected, &OsvRange::unbounded()).unwrap(),
+            affected_ranges(&unaffected).unwrap()
+        );
+        assert_eq!(
+            affected_ranges_within(&unaffected, &range(Some("1.3.0"), Some("1.9.0"))).unwrap(),
+            vec![]
+        );
+    }
+
+    #[cfg(feature = "tracing")]
+    mod events {
+        use super::reqs;
+        use crate::osv::affected_ranges;
+        use std::{
+            fmt,
+            sync::{Arc, Mutex},
+        };
+        use tracing::{
+            field::{Field, Visit},
+            span, Event, Metadata, Subscriber,
+        };
+
+        /// Subscriber recording the fields of all events as text, shared by
+        /// all of its clones
+        #[derive(Clone, Default)]
+        struct EventRecorder(Arc<Mutex<Vec<String>>>);
+
+        impl Subscriber for EventRecorder {
+            fn enabled(&self, _: &Metadata<'_>) -> bool {
+                true
+            }
+
+            fn new_span(&self, _: &span::Attributes<'_>) -> span::Id {
+                span::Id::from_u64(1)
+            }
+
+            fn record(&self, _: &span::Id, _: &span::Record<'_>) {}
+
+            fn record_follows_from(&self, _: &span::Id, _: &span::Id) {}
+
+            fn event(&self, event: &Event<'_>) {
+                struct Fields(String);
+
+                impl Visit for Fields {
+                    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+                        self.0 += &format!("{}={:?} ", field.name(), value);
+                    }
+                }
+
+                let mut fields = Fields(String::new());
+                event.record(&mut fields);
+                self.0.lock().unwrap().push(fields.0.trim_end().to_owned());
+            }
+
+            fn enter(&self, _: &span::Id) {}
+
+            fn exit(&self, _: &span::Id) {}
+        }
+
+        #[test]
+        fn tracing_events() {
+            let recorder = EventRecorder::default();
+
+            tracing::subscriber::with_default(recorder.clone(), || {
+                affected_ranges(&reqs(&[">= 1.2.0, < 2.0.0", "> 2.1.0"])).unwrap();
+            });
+
+            assert_eq!(
+                *recorder.0.lock().unwrap(),
+                vec![
+                    concat!(
+                        "message=converted requirement into ranges ",
+                        "req=>=1.2.0, <2.0.0 ranges=[>=1.2.0, <2.0.0]"
+                    ),
+                    r#"message=converted requirement into ranges req=>2.1.0 ranges=[>2.1.0]"#,
+                    r#"message=merged unaffected ranges count=2 merged=[>=1.2.0, <2.0.0, >2.1.0]"#,
+                    r#"message=computed affected ranges affected=[[*, 1.2.0), [2.0.0, 2.1.0]]"#,
+                ]
+            );
+        }
+    }
+
+    #[test]
+    fn merge_touching_ranges() {
+        assert_eq!(
+            merge_unaffected(unaffected(&["< 1.0.0", "= 1.0.0"])),
+            unaffected(&["<= 1.0.0"])
+        );
+        assert_eq!(
+            merge_unaffected(unaffected(&["< 1.0.0", ">= 1.0.0"])),
+            unaffected(&["*"])
+        );
+        assert_eq!(complement_of(&["< 1.0.0", ">= 1.0.0"]), vec![]);
+
+        // `1.0.0` lies between these
+        assert_eq!(
+            merge_unaffected(unaffected(&["< 1.0.0", "> 1.0.0"])),
+            unaffected(&["< 1.0.0", "> 1.0.0"])
+        );
+    }
+
+    #[test]
+    fn interval_count() {
+        for input in &[
+            vec!["*"],
+            vec!["< 1.0.0", ">= 1.0.0"],
+            vec![],
+            vec![">= 1.2.0"],
+            vec!["<= 1.0.0"],
+            vec!["< 1.0.0", "> 1.0.0"],
+            vec![">= 1.2.0, < 2.0.0", ">= 2.1.0"],
+            vec![">= 1.2.0, < 2.0.0", "> 2.1.0, < 3.0.0", "= 4.0.0"],
+            vec!["<= 1.0.0", ">= 1.0.1-0"],
+        ] {
+            let unaffected = reqs(input);
+            assert_eq!(
+                affected_interval_count(&unaffected).unwrap(),
+                affected_ranges(&unaffected).unwrap().len(),
+                "{:?}",
+                input
+            );
+        }
+
+        assert_eq!(affected_interval_count(&reqs(&["*"])).unwrap(), 0);
+        assert_eq!(affected_interval_count(&reqs(&[">= 1.2.0"])).unwrap(), 1);
+        assert_eq!(
+            affected_interval_count(&reqs(&[">= 1.2.0, < 2.0.0", "> 2.1.0, < 3.0.0"])).unwrap(),
+            3
+        );
+    }
+
+    #[test]
+    fn nothing_affected_above_unbounded_unaffected_range() {
+        let events = |input: &[&str]| {
+            let events: Vec<_> = complement_of(input)
+                .iter()
+                .flat_map(OsvRange::to_osv_events)
+                .collect();
+            serde_json::to_value(events).unwrap()
+        };
+
+        // Exactly one affected range, with nothing introduced past the fix
+        for input in &[
+            vec![">= 1.0.0"],
+            vec![">= 1.0.0", ">= 1.5.0"],
+            vec![">= 1.0.0", ">= 1.2.0, < 1.3.0"],
+            vec![">= 1.0.0", "> 1.0.0"],
+        ] {
+            assert_eq!(
+                events(input),
+                json!([{"introduced": "0.0.0"}, {"fixed": "1.0.0"}]),
+                "{:?}",
+                input
+            );
+        }
+
+        assert_eq!(
+            events(&["< 0.5.0", ">= 1.0.0"]),
+            json!([{"introduced": "0.5.0"}, {"fixed": "1.0.0"}])
+        );
+    }
+
+    #[test]
+    fn disjoint_affected_sets() {
+        let one = reqs(&["< 1.0.0", ">= 2.0.0"]);
+        let two = reqs(&["< 2.0.0", ">= 3.0.0"]);
+        assert!(affected_sets_disjoint(&one, &two).unwrap());
+        assert!(affected_sets_disjoint(&two, &one).unwrap());
+
+        let early = reqs(&["< 1.0.0", ">= 1.5.0"]);
+        let late = reqs(&["< 1.3.0", ">= 1.8.0"]);
+        assert!(!affected_sets_disjoint(&early, &late).unwrap());
+        assert!(!affected_sets_disjoint(&one, &[]).unwrap());
+    }
+
+    #[test]
+    fn conflicting_advisories() {
+        let db = [
+            ("foo", reqs(&["< 1.0.0", ">= 1.5.0"])),
+            ("bar", reqs(&["< 1.0.0", ">= 1.5.0"])),
+            ("foo", reqs(&[">= 1.3.0"])),
+            ("foo", reqs(&["< 1.2.0"])),
+            ("foo", reqs(&[">= 2.0.0, < 1.0.0"])),
+            ("bar", reqs(&[">= 1.2.0"])),
+            // Both affect every version below `1.0.0`
+            ("baz", reqs(&[">= 1.0.0"])),
+            ("baz", reqs(&[">= 2.0.0"])),
+            // Identical advisories conflict on all of their affected versions
+            ("qux", reqs(&[">= 1.0.0"])),
+            ("qux", reqs(&[">= 1.0.0"])),
+        ];
+
+        assert_eq!(
+            find_conflicting_advisories(&db),
+            vec![
+                Conflict {
+                    first: 0,
+                    second: 2,
+                    overlap: range(Some("1.0.0"), Some("1.3.0")),
+                },
+                Conflict {
+                    first: 0,
+                    second: 3,
+                    overlap: range(Some("1.2.0"), Some("1.5.0")),
+                },
+                Conflict {
+                    first: 1,
+                    second: 5,
+                    overlap: range(Some("1.0.0"), Some("1.2.0")),
+                },
+                Conflict {
+                    first: 2,
+                    second: 3,
+                    overlap: range(Some("1.2.0"), Some("1.3.0")),
+                },
+                Conflict {
+                    first: 6,
+                    second: 7,
+                    overlap: range(None, Some("1.0.0")),
+                },
+                Conflict {
+                    first: 8,
+                    second: 9,
+                    overlap: range(None, Some("1.0.0")),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn lazy_affected_ranges() {
+        for input in &[
+            vec![],
+            vec!["*"],
+            vec!["< 1.0.0"],
+            vec![">= 1.0.0"],
+            vec!["> 1.0.0, < 1.5.0", ">= 2.0.0, <= 2.5.0", "> 3.0.0"],
+            vec!["< 1.0.0", ">= 1.0.0, < 2.0.0", ">= 3.0.0"],
+        ] {
+            let patched = reqs(input);
+            let unaffected = all_unaffected_ranges(&patched, &[]).unwrap();
+            assert_eq!(
+                AffectedRanges::new(&unaffected).collect::<Vec<_>>(),
+                affected_ranges(&patched).unwrap(),
+                "{:?}",
+                input
+            );
+        }
+
+        let patched = reqs(&[">= 1.0.0, < 2.0.0"]);
+        let unaffected = all_unaffected_ranges(&patched, &[]).unwrap();
+        let mut iter = AffectedRanges::new(&unaffected);
+        assert_eq!(iter.next(), Some(range(None, Some("1.0.0"))));
+        assert_eq!(iter.next(), Some(range(Some("2.0.0"), None)));
+        assert_eq!(iter.next(), None);
+        assert_eq!(iter.next(), None);
+    }
+
+    #[test]
+    fn upgrade_recommendation() {
+        let patched = reqs(&[">= 1.2.0, < 2.0.0", "> 2.3.0"]);
+        let recommend = |v| recommend_upgrade(&Version::parse(v).unwrap(), &patched).unwrap();
+
+        // Within the same major version
+        assert_eq!(recommend("1.1.0"), Some(Version::new(1, 2, 0)));
+        assert_eq!(recommend("0.9.0"), Some(Version::new(1, 2, 0)));
+        assert_eq!(recommend("2.1.0"), Some(Version::new(2, 3, 1)));
+
+        // Already patched
+        assert_eq!(recommend("1.2.0"), None);
+        assert_eq!(recommend("3.0.0"), None);
+
+        // The only fix is in a higher major version
+        let patched = reqs(&[">= 2.0.0"]);
+        assert_eq!(
+            recommend_upgrade(&Version::new(1, 5, 0), &patched).unwrap(),
+            Some(Version::new(2, 0, 0))
+        );
+
+        // No fix at all
+        assert_eq!(
+            recommend_upgrade(&Version::new(1, 5, 0), &[]).unwrap(),
+            None
+        );
+
+        // The patch version can't go any higher
+        let patched = reqs(&["> 1.2.18446744073709551615"]);
+        assert_eq!(
+            recommend_upgrade(&Version::new(1, 0, 0), &patched).unwrap(),
+            Some(Version::new(1, 3, 0))
+        );
+        let patched = reqs(&["> 18446744073709551615.18446744073709551615.18446744073709551615"]);
+        assert_eq!(
+            recommend_upgrade(&Version::new(1, 0, 0), &patched).unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn affected_prerelease() {
+        let unaffected = reqs(&["< 1.0.0", ">= 1.2.0"]);
+        let check = |v| is_affected_prerelease(&Version::parse(v).unwrap(), &unaffected);
+
+        assert_eq!(check("1.1.0"), None);
+        assert_eq!(check("2.0.0"), None);
+
+        assert_eq!(check("0.9.0-beta"), Some(false));
+        assert_eq!(check("1.0.0-rc.1"), Some(false));
+        assert_eq!(check("1.1.0-alpha"), Some(true));
+        assert_eq!(check("1.2.0-rc.1"), Some(true));
+        // Unaffected by precedence, though `>= 1.2.0` doesn't match it
+        assert_eq!(check("1.3.0-beta"), Some(false));
+    }
+
+    #[test]
+    fn count_published_versions() {
+        let published: Vec<_> = ["0.1.0", "0.2.0", "1.0.0-rc.1", "1.0.0", "1.1.0", "1.3.0"]
+            .iter()
+            .map(|v| Version::parse(v).unwrap())
+            .collect();
+
+        assert_eq!(
+            count_affected_published(&range(Some("1.0.0"), None), &published),
+            3
+        );
+        assert_eq!(
+            count_affected_published(&range(None, Some("1.0.0")), &published),
+            3
+        );
+        assert_eq!(
+            count_affected_published(&range(Some("0.2.0"), Some("1.3.0")), &published),
+            4
+        );
+        assert_eq!(
+            count_affected_published(&range_through(Some("1.0.0"), "1.1.0"), &published),
+            2
+        );
+        assert_eq!(
+            count_affected_published(&OsvRange::unbounded(), &published),
+            6
+        );
+        assert_eq!(
+            count_affected_published(&range(Some("1.2.0"), Some("1.2.5")), &published),
+            0
+        );
+        assert_eq!(count_affected_published(&OsvRange::unbounded(), &[]), 0);
+    }
+
+    #[test]
+    fn snap_to_published_versions() {
+        let published: Vec<_> = ["0.1.0", "0.2.0", "1.0.0", "1.1.0", "1.3.0", "2.0.0"]
+            .iter()
+            .map(|v| Version::parse(v).unwrap())
+            .collect();
+
+        // Both boundaries move up to the next published version
+        assert_eq!(
+            snap_to_versions(&range(Some("0.3.0"), Some("1.2.0")), &published),
+            range(Some("1.0.0"), Some("1.3.0"))
+        );
+        assert_eq!(
+            snap_to_versions(&range(None, Some("0.2.0")), &published),
+            range(Some("0.1.0"), Some("0.2.0"))
+        );
+        assert_eq!(
+            snap_to_versions(&range_through(Some("1.0.0"), "1.1.0"), &published),
+            range(Some("1.0.0"), Some("1.3.0"))
+        );
+
+        // An end above all published versions, or no end, stays put
+        assert_eq!(
+            snap_to_versions(&range(Some("1.2.0"), Some("3.0.0")), &published),
+            range(Some("1.3.0"), Some("3.0.0"))
+        );
+        assert_eq!(
+            snap_to_versions(&range(Some("1.2.0"), None), &published),
+            range(Some("1.3.0"), None)
+        );
+
+        // No published versions in the range
+        let gap = range(Some("1.1.1"), Some("1.3.0"));
+        assert_eq!(snap_to_versions(&gap, &published), gap);
+        assert_eq!(snap_to_versions(&gap, &[]), gap);
+    }
+
+    #[test]
+    fn empty_unaffected_range() {
+        assert_eq!(
+            complement_of(&["<= 1.1.0", ">= 0.2.1, < 0.2.1"]),
+            vec![range(Some("1.1.1-0"), None)]
+        );
+    }
+
+    #[test]
+    fn affected_events() {
+        let v = |s| Version::parse(s).unwrap();
+        let events = |req| {
+            let req = VersionReq::parse(req).unwrap();
+            AffectedEvents::try_from(&req).unwrap().collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            events(">= 1.2.0"),
+            vec![
+                OsvEvent::Introduced(v("0.0.0")),
+                OsvEvent::Fixed(v("1.2.0"))
+            ]
+        );
+        assert_eq!(
+            events("< 1.0.0 || >= 1.2.0, < 2.0.0"),
+            vec![
+                OsvEvent::Introduced(v("1.0.0")),
+                OsvEvent::Fixed(v("1.2.0")),
+                OsvEvent::Introduced(v("2.0.0")),
+            ]
+        );
+        assert_eq!(events("*"), vec![]);
+
+        let invalid = VersionReq::parse(">= 1.0.0, =1.2.0").unwrap();
+        assert!(AffectedEvents::try_from(&invalid).is_err());
+    }
+
+    #[test]
+    fn degenerate_upper_bounds() {
+        // Nothing is below `0.0.0-0`, so everything remains affected
+        assert_eq!(complement_of(&["< 0.0.0-0"]), vec![OsvRange::unbounded()]);
+        assert_eq!(
+            complement_of(&["< 0.0.0-0", "^1.2.0"]),
+            complement_of(&["^1.2.0"])
+        );
+
+        // `< 0.0.0` is just as empty, as are expansions ending there
+        assert_eq!(complement_of(&["< 0.0.0"]), vec![OsvRange::unbounded()]);
+        assert_eq!(complement_of(&["< *"]), vec![OsvRange::unbounded()]);
+        assert_eq!(
+            complement_of(&["< 0.0.0", "^1.2.0"]),
+            complement_of(&["^1.2.0"])
+        );
+        assert_eq!(
+            complement_of(&["<= 0.0.0"]),
+            vec![range(Some("0.0.1-0"), None)]
+        );
+    }
+
+    #[test]
+    fn validate_affected_ranges() {
+        assert_eq!(validate_osv_ranges(&[]), Ok(()));
+        assert_eq!(
+            validate_osv_ranges(&complement_of(&["^1.2.0", ">= 2.1.0", "> 0.5.0, < 1.0.0"])),
+            Ok(())
+        );
+        assert_eq!(
+            validate_osv_ranges(&[
+                range(None, Some("1.0.0")),
+                range(Some("1.0.0"), Some("2.0.0"))
+            ]),
+            Ok(())
+        );
+
+        let overlapping = vec![
+            range(None, Some("1.0.0")),
+            range(Some("1.5.0"), Some("2.0.0")),
+            range(Some("1.8.0"), None),
+        ];
+        assert_eq!(
+            validate_osv_ranges(&overlapping),
+            Err(RangeError::UnorderedRanges(
+                Box::new(overlapping[1].clone()),
+                Box::new(overlapping[2].clone())
+            ))
+        );
+
+        let unordered = vec![
+            range(Some("2.0.0"), None),
+            range(Some("1.0.0"), Some("1.5.0")),
+        ];
+        assert!(validate_osv_ranges(&unordered).is_err());
+        let unordered = vec![
+            range(Some("2.0.0"), Some("2.5.0")),
+            range(Some("1.0.0"), Some("1.5.0")),
+        ];
+        assert!(validate_osv_ranges(&unordered).is_err());
+    }
+
+    /// Versions clustered around a few releases, so generated bounds often
+    /// coincide, including pre-releases of the same versions
+    fn arb_version() -> impl Strategy<Value = Version> {
+        (0..3u64, 0..3u64, 0..2u64, 0..4usize).prop_map(|(major, minor, patch, pre)| {
+            let pre = ["", "-0", "-alpha", "-beta.1"][pre];
+            Version::parse(&format!("{}.{}.{}{}", major, minor, patch, pre)).unwrap()
+        })
+    }
+
+    fn arb_requirement() -> impl Strategy<Value = VersionReq> {
+        (0..8usize, arb_version(), arb_version()).prop_map(|(form, a, b)| {
+            let (low, high) = if a <= b { (a, b) } else { (b, a) };
+            let req = match form {
+                0 => format!(">= {}", low),
+                1 => format!("> {}", low),
+                2 => format!("< {}", high),
+                3 => format!("<= {}", high),
+                4 => format!("= {}", low),
+                5 => format!(">= {}, < {}", low, high),
+                6 => format!("> {}, <= {}", low, high),
+                _ => format!(">= {}, <= {} || > {}", low, low, high),
+            };
+            VersionReq::parse(&req).unwrap()
+        })
+    }
+
+    proptest! {
+        #[test]
+        fn affected_ranges_agree_with_is_version_affected(
+            unaffected in proptest::collection::vec(arb_requirement(), 0..5),
+            probes in proptest::collection::vec(arb_version(), 1..20),
+        ) {
+            let affected = affected_ranges(&unaffected);
+            prop_assume!(affected.is_ok());
+            let affected = affected.unwrap();
+
+            for probe in &probes {
+                prop_assert_eq!(
+                    is_version_affected(probe, &unaffected),
+                    affected.iter().any(|range| range.contains(probe)),
+                    "{} with unaffected {:?}, affected {:?}",
+                    probe,
+                    unaffected.iter().map(ToString::to_string).collect::<Vec<_>>(),
+                    affected.iter().map(ToString::to_string).collect::<Vec<_>>()
+                );
+            }
+        }
+    }
+}
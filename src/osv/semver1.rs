@@ -0,0 +1,161 @@
+//! Conversion of requirements from the `semver` 1.x API into ranges.
+//!
+//! The comparators of a `semver` 1.x requirement keep the operator and
+//! partial version they were written with, so they are converted the same
+//! way as requirement text, including Cargo's reading of `> 1.2` as
+//! `>= 1.3.0`. Caret, tilde, wildcard and partial version expansion happens
+//! in the shared `expand_comparator`.
+
+use super::{
+    unaffected_range::{parse_identifiers, single_range, ReqComparator, ReqOp},
+    RangeError, UnaffectedRange,
+};
+use semver1::{Comparator, Op, VersionReq};
+
+impl UnaffectedRange {
+    /// Convert a requirement from the `semver` 1.x API into the ranges of
+    /// versions it describes.
+    ///
+    /// This behaves the same as parsing the text of the requirement, e.g.
+    /// via [`AuthoredRange::parse`](super::AuthoredRange::parse).
+    /// Requirements without comparators (i.e. `*`) cover all versions.
+    pub fn from_semver1_req(req: &VersionReq) -> Result<Vec<Self>, RangeError> {
+        let comparators = req
+            .comparators
+            .iter()
+            .map(req_comparator)
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| e.in_requirement(req))?;
+
+        let range = single_range(&comparators).map_err(|e| e.in_requirement(req))?;
+        Ok(vec![range])
+    }
+}
+
+/// Convert a `semver` 1.x comparator into the comparator as written
+fn req_comparator(comparator: &Comparator) -> Result<ReqComparator, RangeError> {
+    let op = match comparator.op {
+        Op::Exact => ReqOp::Exact,
+        Op::Greater => ReqOp::Greater,
+        Op::GreaterEq => ReqOp::GreaterEq,
+        Op::Less => ReqOp::Less,
+        Op::LessEq => ReqOp::LessEq,
+        Op::Tilde => ReqOp::Tilde,
+        Op::Caret => ReqOp::Caret,
+        Op::Wildcard => ReqOp::Wildcard,
+        _ => return Err(RangeError::UnsupportedComparator(comparator.to_string())),
+    };
+
+    Ok(ReqComparator {
+        op,
+        major: comparator.major,
+        minor: comparator.minor,
+        patch: comparator.patch,
+        pre: parse_identifiers(comparator.pre.as_str()),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::osv::{AuthoredRange, RangeError, UnaffectedRange};
+
+    /// Check that a `semver` 1.x requirement converts to the same ranges as
+    /// the given requirement text does
+    fn check(req: &str, expected: &str) {
+        let actual = UnaffectedRange::from_semver1_req(&semver1::VersionReq::parse(req).unwrap());
+        let expected: Vec<_> = AuthoredRange::parse(expected)
+            .unwrap()
+            .iter()
+            .map(|range| range.range().clone())
+            .collect();
+        assert_eq!(actual.unwrap(), expected, "{}", req);
+    }
+
+    #[test]
+    fn exact() {
+        check("=1.2.3", "=1.2.3");
+        check("=1.2.3-beta.1", "=1.2.3-beta.1");
+        check("=1.2", ">= 1.2.0, < 1.3.0");
+        check("=1", ">= 1.0.0, < 2.0.0");
+    }
+
+    #[test]
+    fn greater() {
+        check(">1.2.3", "> 1.2.3");
+        check(">1.2", ">= 1.3.0");
+        check(">1", ">= 2.0.0");
+        check(">1.18446744073709551615", ">= 2.0.0");
+
+        // There is no version above the highest one
+        let req = semver1::VersionReq::parse(">18446744073709551615").unwrap();
+        assert_eq!(
+            UnaffectedRange::from_semver1_req(&req),
+            Err(RangeError::EmptyRange.in_requirement(&req))
+        );
+    }
+
+    #[test]
+    fn greater_eq() {
+        check(">=1.2.3", ">= 1.2.3");
+        check(">=1.2.3-rc.1", ">= 1.2.3-rc.1");
+        check(">=1.2", ">= 1.2.0");
+        check(">=0", "*");
+    }
+
+    #[test]
+    fn less() {
+        check("<1.2.3", "< 1.2.3");
+        check("<1.2", "< 1.2");
+        check("<2", "< 2");
+        check("<2.0.0", "< 2.0.0");
+    }
+
+    #[test]
+    fn less_eq() {
+        check("<=1.2.3", "<= 1.2.3");
+        check("<=1.2", "< 1.3.0");
+        check("<=1", "< 2.0.0");
+        check("<=1.18446744073709551615", "< 2.0.0");
+        check("<=18446744073709551615", "*");
+    }
+
+    #[test]
+    fn tilde() {
+        check("~1.2.3", ">= 1.2.3, < 1.3.0");
+        check("~1.2", ">= 1.2.0, < 1.3.0");
+        check("~1", ">= 1.0.0, < 2.0.0");
+    }
+
+    #[test]
+    fn caret() {
+        check("^1.2.3", ">= 1.2.3, < 2.0.0");
+        check("^1.2", ">= 1.2.0, < 2.0.0");
+        check("^0.2.3", ">= 0.2.3, < 0.3.0");
+        check("^0.0.3", ">= 0.0.3, < 0.0.4");
+        check("^0.0", ">= 0.0.0, < 0.1.0");
+        check("^0", ">= 0.0.0, < 1.0.0");
+        check("1.2.3", ">= 1.2.3, < 2.0.0");
+    }
+
+    #[test]
+    fn wildcard() {
+        check("1.2.*", ">= 1.2.0, < 1.3.0");
+        check("1.*", ">= 1.0.0, < 2.0.0");
+        check("*", "*");
+    }
+
+    #[test]
+    fn multiple_comparators() {
+        check(">=1.2.0, <1.5.0", ">= 1.2.0, < 1.5.0");
+        for (req, error) in &[
+            (">=1.0.0, <1.5.0, >=2.0.0", RangeError::TooManyPredicates),
+            (">=1, =1.2.0", RangeError::ExactVersionWithOtherPredicates),
+        ] {
+            let req = semver1::VersionReq::parse(req).unwrap();
+            assert_eq!(
+                UnaffectedRange::from_semver1_req(&req),
+                Err(error.clone().in_requirement(&req))
+            );
+        }
+    }
+}
@@ -0,0 +1,2370 @@
+//! Ranges of versions which are not affected by an advisory, i.e. the
+//! versions specified by its `patched` and `unaffected` fields.
+//!
+//! `semver::VersionReq` can only tell whether a given version matches it,
+//! but exporting advisories in the OSV format requires knowing the exact
+//! boundaries of the unaffected ranges so they can be inverted into ranges
+//! of affected versions. We reconstruct them here from the comparators
+//! produced by `semver-parser`, which `semver` uses under the hood.
+//!
+//! Pre-releases are handled correctly, i.e. they are treated as regular
+//! points on the version line rather than following the special matching
+//! rules of `VersionReq` (see <https://github.com/steveklabnik/semver/issues/172>).
+//! A range contains every version which lies within its bounds according to
+//! semver precedence, so `1.0.0-beta` is contained in `>= 1.0.0-alpha` and
+//! `>= 0.9.0`, but not in `>= 1.0.0` since `1.0.0-beta < 1.0.0`.
+//!
+//! Requirements are converted from the comparators they were written with:
+//! caret (`^`), tilde (`~`), wildcard and partial versions are all expanded
+//! into bounds by `expand_comparator` following Cargo's rules, including the
+//! special pre-1.0 caret semantics, so e.g. `^0.1.2` is `>= 0.1.2, < 0.2.0`
+//! and `^0.0.3` is `>= 0.0.3, < 0.0.4`. A bare version without an operator is
+//! a caret requirement, so `1.2.3` is `>= 1.2.3, < 2.0.0` as it is in Cargo.
+//! Missing components of partial versions are filled with zeros (`>= 2.0` is
+//! `>= 2.0.0`) unless the operator covers them (`<= 2.0` is `< 2.1.0` and
+//! `> 2.0` is `>= 2.1.0`). A bare major upper bound such as `< 2` is
+//! therefore the same as `< 2.0.0`, and contains the pre-releases of `2.0.0`
+//! like any other exclusive end, whether it's converted from text or from a
+//! `VersionReq`. Together with `>= 2`, it covers every version exactly once.
+//!
+//! A `VersionReq` only retains the comparators `semver-parser` has already
+//! expanded them into, and `semver-parser` reads `> 1.2` as `> 1.2.0`, so
+//! that's what converting one (e.g. the `patched` field of a parsed advisory)
+//! results in. Converting the requirement text via
+//! [`UnaffectedRange::from_str`] or [`AuthoredRange::parse`] uses the
+//! comparators as they were written instead. Use
+//! [`cargo_matches`](super::cargo_matches) for whether Cargo would select a
+//! particular pre-release.
+//!
+//! Each `||`-separated range may have at most one lower and one upper bound.
+//! `semver` only matches versions which satisfy all of a range's comparators,
+//! so e.g. `>= 1.0, < 1.5, >= 2.0` matches no versions at all rather than
+//! both `1.0 - 1.5` and `2.0` onwards, and is rejected. Disjoint ranges are
+//! written with `||`, as in `>= 1.0, < 1.5 || >= 2.0`.
+
+use super::{range::without_build, ranges::increment, RangeError};
+use semver::{Identifier, Version, VersionReq};
+use semver_parser::{Comparator, Op, Range};
+use serde::{de::Error as _, Deserialize, Deserializer};
+use std::{cmp::Ordering, convert::TryFrom, fmt, str::FromStr};
+
+#[cfg(feature = "tracing")]
+use tracing::debug;
+
+/// A range of unaffected versions, used by either `patched` or `unaffected`
+/// fields in the security advisory.
+///
+/// Each side of the range is described by a [`Bound`], which may be
+/// inclusive or exclusive of the version it's placed at, or unbounded.
+///
+/// # Example
+///
+/// ```
+/// use rustsec::{
+///     osv::{Bound, UnaffectedRange},
+///     Version,
+/// };
+///
+/// // `>= 1.0.0, < 2.0.0`
+/// let a = UnaffectedRange::new(
+///     Bound::Inclusive(Version::new(1, 0, 0)),
+///     Bound::Exclusive(Version::new(2, 0, 0)),
+/// )
+/// .unwrap();
+///
+/// // `>= 2.0.0`
+/// let b = UnaffectedRange::new(Bound::Inclusive(Version::new(2, 0, 0)), Bound::Unbounded).unwrap();
+///
+/// // `<= 1.5.0`
+/// let c = UnaffectedRange::new(Bound::Unbounded, Bound::Inclusive(Version::new(1, 5, 0))).unwrap();
+///
+/// assert!(!a.overlaps(&b));
+/// assert!(a.overlaps(&c));
+/// ```
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct UnaffectedRange {
+    start: Bound,
+    end: Bound,
+    origin: RangeOrigin,
+}
+
+/// Why the versions in an [`UnaffectedRange`] are not affected.
+///
+/// This isn't carried over into the OSV export: OSV has no event marking
+/// where versions which were never affected start, so affected ranges end
+/// with a `fixed` event either way.
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq, PartialOrd, Ord)]
+pub enum RangeOrigin {
+    /// The versions contain a fix for the vulnerability (the `patched` field
+    /// of an advisory)
+    Patched,
+
+    /// The versions were never affected in the first place (the `unaffected`
+    /// field of an advisory)
+    Unaffected,
+}
+
+// `#[default]` on enum variants requires a newer Rust than our MSRV
+#[allow(clippy::derivable_impls)]
+impl Default for RangeOrigin {
+    fn default() -> Self {
+        RangeOrigin::Patched
+    }
+}
+
+impl UnaffectedRange {
+    /// Create a new range from the given bounds.
+    ///
+    /// Returns [`RangeError::InvalidRange`] if the start of the range lies
+    /// above its end. Build metadata is stripped from both bounds.
+    ///
+    /// The range is considered [`RangeOrigin::Patched`] unless changed via
+    /// [`UnaffectedRange::with_origin`].
+    pub fn new(start: Bound, end: Bound) -> Result<Self, RangeError> {
+        let result = UnaffectedRange {
+            start: start.without_build(),
+            end: end.without_build(),
+            origin: RangeOrigin::default(),
+        };
+
+        if result.is_valid() {
+            Ok(result)
+        } else {
+            Err(RangeError::InvalidRange)
+        }
+    }
+
+    /// Convert a version requirement into the ranges of versions it
+    /// describes, one or more for each `||`-separated part.
+    ///
+    /// Comma-separated comparators are combined into a single range, which
+    /// may have at most one lower and one upper bound.
+    pub fn from_version_req(req: &VersionReq) -> Result<Vec<Self>, RangeError> {
+        ranges_from_version_req(req)
+    }
+
+    /// Set why the versions in this range are not affected
+    pub fn with_origin(mut self, origin: RangeOrigin) -> Self {
+        self.origin = origin;
+        self
+    }
+
+    /// Get why the versions in this range are not affected
+    pub fn origin(&self) -> RangeOrigin {
+        self.origin
+    }
+
+    /// Get the lower bound of this range
+    pub fn start(&self) -> &Bound {
+        &self.start
+    }
+
+    /// Get the upper bound of this range
+    pub fn end(&self) -> &Bound {
+        &self.end
+    }
+
+    /// Is the start of this range at or below its end?
+    pub fn is_valid(&self) -> bool {
+        match (self.start.version(), self.end.version()) {
+            (Some(start), Some(end)) => start <= end,
+            _ => true,
+        }
+    }
+
+    /// Does this range contain no versions at all?
+    ///
+    /// This is the case for ranges with no version between their bounds, e.g.
+    /// `>= 1.0.0, < 1.0.0` or `> 1.0.0, < 1.0.1-0`, and for those ending at
+    /// `< 0.0.0-0`, since that's the lowest version. Ranges ending at
+    /// `< 0.0.0` are empty as well: it's what `semver-parser` reads `< *` as,
+    /// and the pre-releases of `0.0.0` it would technically contain are never
+    /// meant by it.
+    pub fn is_empty(&self) -> bool {
+        !precedes(&self.start, &self.end)
+    }
+
+    /// Get a normalized requirement string for this range, which is the same
+    /// for all ranges containing exactly the same versions.
+    ///
+    /// Every range is described by an inclusive start and exclusive end, as
+    /// in `>=1.0.0, <2.0.0`, so the other kinds of bounds are replaced with
+    /// their equivalent by precedence: `> 1.0.0` becomes `>=1.0.1-0`, and
+    /// `= 1.0.0` becomes `>=1.0.0, <1.0.1-0`. Unbounded sides are omitted,
+    /// or `*` if both are unbounded. Empty ranges are all `<0.0.0-0`.
+    pub fn canonical_form(&self) -> String {
+        if self.is_empty() {
+            return "<0.0.0-0".to_owned();
+        }
+
+        let start = match &self.start {
+            Bound::Unbounded => None,
+            Bound::Inclusive(v) => Some(v.clone()),
+            Bound::Exclusive(v) => Some(increment(v)),
+        };
+        let end = match &self.end {
+            Bound::Unbounded => None,
+            Bound::Inclusive(v) => Some(increment(v)),
+            Bound::Exclusive(v) => Some(v.clone()),
+        };
+
+        match (start, end) {
+            (Some(start), Some(end)) => format!(">={}, <{}", start, end),
+            (Some(start), None) => format!(">={}", start),
+            (None, Some(end)) => format!("<{}", end),
+            (None, None) => "*".to_owned(),
+        }
+    }
+
+    /// Is the given version within this range?
+    ///
+    /// Pre-releases are compared by semver precedence like any other version,
+    /// so `2.0.0-rc.1` is within `> 1.0.0, <= 2.0.0`.
+    pub fn contains(&self, version: &Version) -> bool {
+        let version = Bound::Inclusive(version.clone());
+        !self.is_empty() && precedes(&self.start, &version) && precedes(&version, &self.end)
+    }
+
+    /// Do the two ranges have at least one version in common?
+    ///
+    /// Empty and invalid ranges never overlap with anything.
+    pub fn overlaps(&self, other: &UnaffectedRange) -> bool {
+        if !self.is_valid() || !other.is_valid() || self.is_empty() || other.is_empty() {
+            return false;
+        }
+
+        // The range check for well-formed ranges is
+        // `(start1 <= end2) && (start2 <= end1)`
+        precedes(&self.start, &other.end) && precedes(&other.start, &self.end)
+    }
+
+    /// Do the two ranges border on each other without overlapping, i.e. does
+    /// one of them end exactly where the other one starts, e.g. `< 1.0.0`
+    /// and `= 1.0.0`?
+    ///
+    /// Adjacent ranges can be merged into a single one covering the same
+    /// versions, unlike ranges with a version in between, such as `< 1.0.0`
+    /// and `> 1.0.0`. Empty and invalid ranges are never adjacent.
+    pub fn is_adjacent(&self, other: &UnaffectedRange) -> bool {
+        if !self.is_valid() || !other.is_valid() || self.is_empty() || other.is_empty() {
+            return false;
+        }
+
+        touches(&self.end, &other.start) || touches(&other.end, &self.start)
+    }
+
+    /// Extend this range to also cover the other one, if they overlap or are
+    /// adjacent, so that the result contains exactly the versions of both.
+    ///
+    /// Returns whether the ranges were merged; otherwise this range is left
+    /// unchanged. The merged range keeps the origin of the range starting
+    /// first, preferring [`RangeOrigin::Patched`] if both start at the same
+    /// bound.
+    pub fn merge_with(&mut self, other: &UnaffectedRange) -> bool {
+        if !self.overlaps(other) && !self.is_adjacent(other) {
+            return false;
+        }
+
+        match StartBound(&other.start).cmp(&StartBound(&self.start)) {
+            Ordering::Less => {
+                self.start = other.start.clone();
+                self.origin = other.origin;
+            }
+            Ordering::Equal if other.origin == RangeOrigin::Patched => {
+                self.origin = RangeOrigin::Patched;
+            }
+            _ => {}
+        }
+
+        if EndBound(&self.end) < EndBound(&other.end) {
+            self.end = other.end.clone();
+        }
+
+        true
+    }
+
+    /// Do the two ranges start and end at the same versions, regardless of
+    /// whether those are included?
+    ///
+    /// This is a looser comparison than `Eq`: `>= 1.0.0, < 2.0.0` and
+    /// `> 1.0.0, <= 2.0.0` are the same by this measure, as are ranges with
+    /// different origins. An unbounded side only matches an unbounded side.
+    pub fn same_versions_ignoring_inclusivity(&self, other: &UnaffectedRange) -> bool {
+        self.start.version() == other.start.version() && self.end.version() == other.end.version()
+    }
+
+    /// Is every version in the other range also contained in this one?
+    ///
+    /// Empty ranges are contained in every range.
+    pub fn contains_range(&self, other: &UnaffectedRange) -> bool {
+        if other.is_empty() {
+            return true;
+        }
+
+        StartBound(&self.start) <= StartBound(&other.start)
+            && EndBound(&other.end) <= EndBound(&self.end)
+    }
+
+    /// Get the parts of this range which aren't covered by the other one.
+    ///
+    /// This is a single range if the other range covers one end of this one
+    /// or doesn't overlap it at all, two ranges if it lies strictly inside
+    /// this one, and nothing if it contains this range entirely.
+    pub fn subtract(&self, other: &UnaffectedRange) -> Vec<UnaffectedRange> {
+        if self.is_empty() {
+            return vec![];
+        }
+
+        if !self.overlaps(other) {
+            return vec![self.clone()];
+        }
+
+        let mut result = vec![];
+
+        // Versions below the other range, up to where it starts
+        if StartBound(&self.start) < StartBound(&other.start) {
+            let below = UnaffectedRange {
+                start: self.start.clone(),
+                end: other.start.flipped(),
+                origin: self.origin,
+            };
+
+            if !below.is_empty() {
+                result.push(below);
+            }
+        }
+
+        // Versions above the other range, starting where it ends
+        if EndBound(&other.end) < EndBound(&self.end) {
+            let above = UnaffectedRange {
+                start: other.end.flipped(),
+                end: self.end.clone(),
+                origin: self.origin,
+            };
+
+            if !above.is_empty() {
+                result.push(above);
+            }
+        }
+
+        result
+    }
+
+    /// Get the range of versions contained in both this range and the other
+    /// one, if there are any
+    pub fn intersection(&self, other: &UnaffectedRange) -> Option<UnaffectedRange> {
+        if !self.overlaps(other) {
+            return None;
+        }
+
+        let start = std::cmp::max(StartBound(&self.start), StartBound(&other.start));
+        let end = std::cmp::min(EndBound(&self.end), EndBound(&other.end));
+
+        Some(UnaffectedRange {
+            start: start.0.clone(),
+            end: end.0.clone(),
+            origin: self.origin,
+        })
+    }
+}
+
+impl Default for UnaffectedRange {
+    fn default() -> Self {
+        UnaffectedRange {
+            start: Bound::Unbounded,
+            end: Bound::Unbounded,
+            origin: RangeOrigin::default(),
+        }
+    }
+}
+
+impl fmt::Display for UnaffectedRange {
+    /// Render the range as a version requirement, e.g. `>=1.0.0, <2.0.0`
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (&self.start, &self.end) {
+            (Bound::Unbounded, Bound::Unbounded) => write!(f, "*"),
+            (Bound::Inclusive(start), Bound::Inclusive(end)) if start == end => {
+                write!(f, "={}", start)
+            }
+            (Bound::Unbounded, end) => write!(f, "{}", EndBound(end)),
+            (start, Bound::Unbounded) => write!(f, "{}", StartBound(start)),
+            (start, end) => write!(f, "{}, {}", StartBound(start), EndBound(end)),
+        }
+    }
+}
+
+impl FromStr for UnaffectedRange {
+    type Err = RangeError;
+
+    /// Parse a version requirement describing a single range, such as
+    /// `>= 1.0.0, < 2.0.0`, which may have at most two comparators
+    fn from_str(input: &str) -> Result<Self, RangeError> {
+        let mut ranges = parse_comparators(input)?;
+
+        if ranges.len() != 1 {
+            return Err(RangeError::MultipleRanges);
+        }
+
+        single_range(&ranges.remove(0))
+    }
+}
+
+impl TryFrom<Range> for UnaffectedRange {
+    type Error = RangeError;
+
+    fn try_from(input: Range) -> Result<Self, RangeError> {
+        let comparators: Vec<_> = input
+            .comparator_set
+            .iter()
+            .map(ReqComparator::from)
+            .collect();
+        single_range(&comparators)
+    }
+}
+
+/// A single-range requirement to be converted into an [`UnaffectedRange`]
+/// strictly, i.e. only if it states its bounds explicitly.
+///
+/// The conversion fails with [`RangeError::ImplicitBound`] if the range is
+/// written with an operator that implies its bounds, i.e. caret, tilde,
+/// wildcard or bare versions (such as `^1.2.3`, `~1.2`, `1.*` or `1.2.3`)
+/// or `=` with a partial version, or if it's missing a lower or upper bound,
+/// so e.g. `>= 1.0.0` and `*` are rejected. A lower bound of `0.0.0` counts
+/// as missing, since it leaves the range unbounded. Exact versions such as
+/// `=1.2.3` are accepted. The requirement is read as written, since `semver`
+/// would already have expanded the implied bounds.
+#[derive(Clone, Copy, Debug)]
+pub struct StrictConversion<'a>(pub &'a str);
+
+impl TryFrom<StrictConversion<'_>> for UnaffectedRange {
+    type Error = RangeError;
+
+    fn try_from(input: StrictConversion<'_>) -> Result<Self, RangeError> {
+        let mut ranges = parse_comparators(input.0)?;
+
+        if ranges.len() != 1 {
+            return Err(RangeError::MultipleRanges);
+        }
+
+        let comparators = ranges.remove(0);
+        let result = single_range(&comparators)?;
+
+        let implied = comparators.iter().any(|comparator| match comparator.op {
+            ReqOp::Tilde | ReqOp::Caret | ReqOp::Wildcard => true,
+            ReqOp::Exact => !comparator.is_exact(),
+            _ => false,
+        });
+        let exact = comparators.len() == 1 && comparators[0].is_exact();
+        let bounded = result.start != Bound::Unbounded && result.end != Bound::Unbounded;
+
+        if !implied && (exact || bounded) {
+            Ok(result)
+        } else {
+            Err(RangeError::ImplicitBound)
+        }
+    }
+}
+
+/// Convert a version requirement into the ranges of versions it describes.
+///
+/// Each range of the requirement (i.e. each `||`-separated part) is converted
+/// into one range, so `>= 1.0, < 1.5 || >= 2.0` describes `[1.0, 1.5)` and
+/// `[2.0, ∞)`. A range with more than two bounds, such as
+/// `>= 1.0, < 1.5, >= 2.0`, is rejected with
+/// [`RangeError::TooManyPredicates`], since `semver` requires versions to
+/// match all of them.
+#[cfg_attr(not(feature = "tracing"), allow(clippy::let_and_return))]
+pub(crate) fn ranges_from_version_req(
+    req: &VersionReq,
+) -> Result<Vec<UnaffectedRange>, RangeError> {
+    // `VersionReq` displays the normalized comparators it was parsed into
+    let result = ranges_from_str(&req.to_string());
+
+    #[cfg(feature = "tracing")]
+    match &result {
+        Ok(ranges) => {
+            debug!(req = %req, ranges = ?DisplayList(ranges), "converted requirement into ranges")
+        }
+        Err(e) => debug!(req = %req, error = %e, "failed to convert requirement into ranges"),
+    }
+
+    result
+}
+
+/// Convert the text of a version requirement into the ranges of versions it
+/// describes, like [`ranges_from_version_req`]
+fn ranges_from_str(req: &str) -> Result<Vec<UnaffectedRange>, RangeError> {
+    let mut result = vec![];
+
+    for comparators in parse_comparators(req)? {
+        result.push(single_range(&comparators).map_err(|e| e.in_requirement(req))?);
+    }
+
+    Ok(result)
+}
+
+/// Deserialize a list of version requirement strings, such as the `patched`
+/// field of an advisory, directly into the unaffected ranges they describe.
+///
+/// Intended for use with `#[serde(deserialize_with = "...")]`. Requirements
+/// which fail to parse or convert are reported as deserialization errors.
+pub fn deserialize_ranges<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Vec<UnaffectedRange>, D::Error> {
+    let mut result = vec![];
+
+    for string in Vec::<String>::deserialize(deserializer)? {
+        VersionReq::parse(&string).map_err(|e| {
+            D::Error::custom(format!("invalid version requirement \"{}\": {}", string, e))
+        })?;
+        result.extend(ranges_from_str(&string).map_err(D::Error::custom)?);
+    }
+
+    Ok(result)
+}
+
+/// Convert the comparators of a single `||`-separated range into one range,
+/// which may have at most one lower and one upper bound
+pub(super) fn single_range(comparators: &[ReqComparator]) -> Result<UnaffectedRange, RangeError> {
+    let (starts, ends) = expand_range(comparators)?;
+
+    if starts.len() + ends.len() > 2 {
+        return Err(RangeError::TooManyPredicates);
+    }
+
+    range_from_bounds(starts, ends)
+}
+
+/// Expand the comparators of a single `||`-separated range into its lower
+/// and upper bounds, leaving out the sides they leave unbounded
+fn expand_range(comparators: &[ReqComparator]) -> Result<(Vec<Bound>, Vec<Bound>), RangeError> {
+    // An exact version provides both of the range's bounds on its own
+    if comparators.iter().any(ReqComparator::is_exact) && comparators.len() > 1 {
+        return Err(RangeError::ExactVersionWithOtherPredicates);
+    }
+
+    let mut starts = vec![];
+    let mut ends = vec![];
+
+    for comparator in comparators {
+        let (start, end) = expand_comparator(comparator)?;
+
+        if start != Bound::Unbounded {
+            starts.push(start);
+        }
+        if end != Bound::Unbounded {
+            ends.push(end);
+        }
+    }
+
+    Ok((starts, ends))
+}
+
+/// Create the range with at most one of each of the given bounds
+fn range_from_bounds(
+    mut starts: Vec<Bound>,
+    mut ends: Vec<Bound>,
+) -> Result<UnaffectedRange, RangeError> {
+    if starts.len() > 1 {
+        return Err(RangeError::MultipleLowerBounds);
+    }
+    if ends.len() > 1 {
+        return Err(RangeError::MultipleUpperBounds);
+    }
+
+    // A range without bounds matches every version, just like `*`
+    UnaffectedRange::new(
+        starts.pop().unwrap_or(Bound::Unbounded),
+        ends.pop().unwrap_or(Bound::Unbounded),
+    )
+}
+
+/// A comparator of a version requirement as it was written, before caret,
+/// tilde, wildcard and partial versions are expanded into bounds
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub(super) struct ReqComparator {
+    pub(super) op: ReqOp,
+    pub(super) major: u64,
+    pub(super) minor: Option<u64>,
+    pub(super) patch: Option<u64>,
+    pub(super) pre: Vec<Identifier>,
+}
+
+/// Operator of a [`ReqComparator`]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(super) enum ReqOp {
+    /// `=`
+    Exact,
+    /// `>`
+    Greater,
+    /// `>=`
+    GreaterEq,
+    /// `<`
+    Less,
+    /// `<=`
+    LessEq,
+    /// `~` (or `~>`)
+    Tilde,
+    /// `^`, or no operator
+    Caret,
+    /// No operator, with a wildcard component such as `1.*`
+    Wildcard,
+}
+
+impl ReqComparator {
+    /// Is this an exact version, which makes up a range of its own?
+    fn is_exact(&self) -> bool {
+        self.op == ReqOp::Exact && self.patch.is_some()
+    }
+}
+
+impl From<&Comparator> for ReqComparator {
+    /// Convert one of the primitive comparators `semver-parser` expands
+    /// requirements into
+    fn from(comparator: &Comparator) -> Self {
+        let op = match comparator.op {
+            Op::Eq => ReqOp::Exact,
+            Op::Gt => ReqOp::Greater,
+            Op::Gte => ReqOp::GreaterEq,
+            Op::Lt => ReqOp::Less,
+            Op::Lte => ReqOp::LessEq,
+        };
+
+        ReqComparator {
+            op,
+            major: comparator.major,
+            minor: Some(comparator.minor),
+            patch: Some(comparator.patch),
+            pre: comparator_version(comparator).pre,
+        }
+    }
+}
+
+/// Expand a comparator into the bounds of the range of versions it matches,
+/// the way Cargo interprets it.
+///
+/// Missing components of a partial version are filled with zeros where the
+/// range starts at it, so `>= 1.2` starts at `1.2.0`. Otherwise the comparator
+/// covers all versions starting with the given components: `<= 1.2` ends
+/// below `1.3.0`, and `> 1.2` starts at `1.3.0`.
+///
+/// Caret requirements allow changes which don't modify the leftmost non-zero
+/// component, so `^0.2.3` ends below `0.3.0` and `^0.0.3` below `0.0.4`.
+/// Tilde requirements pin the major and minor versions if given.
+///
+/// A start at `0.0.0` is unbounded, which makes a wildcard cover all versions
+/// (including pre-releases of `0.0.0`, which are lower). Ends past the
+/// highest possible version are unbounded too, so e.g.
+/// `^18446744073709551615` has no end, while a start past it, as in
+/// `> 18446744073709551615`, matches nothing and is reported as
+/// [`RangeError::EmptyRange`].
+pub(super) fn expand_comparator(comparator: &ReqComparator) -> Result<(Bound, Bound), RangeError> {
+    use ReqOp::*;
+
+    let (major, minor, patch) = (comparator.major, comparator.minor, comparator.patch);
+    let version = Version {
+        major,
+        minor: minor.unwrap_or(0),
+        patch: patch.unwrap_or(0),
+        pre: comparator.pre.clone(),
+        build: vec![],
+    };
+
+    let start = if version == Version::new(0, 0, 0) {
+        Bound::Unbounded
+    } else {
+        Bound::Inclusive(version.clone())
+    };
+    let end = |next: Option<Version>| next.map_or(Bound::Unbounded, Bound::Exclusive);
+    let is_partial = patch.is_none();
+
+    Ok(match comparator.op {
+        Exact if is_partial => (start, end(next_version(major, minor, None))),
+        Exact => (Bound::Inclusive(version.clone()), Bound::Inclusive(version)),
+        Greater if is_partial => {
+            let next = next_version(major, minor, None).ok_or(RangeError::EmptyRange)?;
+            (Bound::Inclusive(next), Bound::Unbounded)
+        }
+        Greater => (Bound::Exclusive(version), Bound::Unbounded),
+        GreaterEq => (start, Bound::Unbounded),
+        Less => (Bound::Unbounded, Bound::Exclusive(version)),
+        LessEq if is_partial => (Bound::Unbounded, end(next_version(major, minor, None))),
+        LessEq => (Bound::Unbounded, Bound::Inclusive(version)),
+        Tilde => (start, end(next_version(major, minor, None))),
+        Caret => {
+            let next = match (major, minor, patch) {
+                (0, Some(0), Some(_)) => next_version(major, minor, patch),
+                (0, Some(_), _) => next_version(major, minor, None),
+                _ => next_version(major, None, None),
+            };
+            (start, end(next))
+        }
+        Wildcard => (start, end(next_version(major, minor, None))),
+    })
+}
+
+/// Get the lowest version above all versions starting with the given
+/// components, e.g. `1.3.0` for `1.2`, carrying over into the next component
+/// if the last one can't go any higher.
+///
+/// Returns `None` if there is no such version.
+fn next_version(major: u64, minor: Option<u64>, patch: Option<u64>) -> Option<Version> {
+    match (minor, patch) {
+        (Some(minor), Some(patch)) => match patch.checked_add(1) {
+            Some(patch) => Some(Version::new(major, minor, patch)),
+            None => next_version(major, Some(minor), None),
+        },
+        (Some(minor), None) => match minor.checked_add(1) {
+            Some(minor) => Some(Version::new(major, minor, 0)),
+            None => next_version(major, None, None),
+        },
+        (None, _) => major.checked_add(1).map(|major| Version::new(major, 0, 0)),
+    }
+}
+
+/// Parse a version requirement into the comparators of each of its
+/// `||`-separated ranges, as they were written.
+///
+/// The requirement is validated by `VersionReq::parse` first, so exactly the
+/// same requirements are accepted.
+pub(super) fn parse_comparators(req: &str) -> Result<Vec<Vec<ReqComparator>>, RangeError> {
+    VersionReq::parse(req).map_err(RangeError::InvalidRequirement)?;
+
+    Ok(req.split("||").map(parse_range_comparators).collect())
+}
+
+/// Parse the comparators of a single `||`-separated range
+fn parse_range_comparators(range: &str) -> Vec<ReqComparator> {
+    let words: Vec<_> = range
+        .split(|c| c == ',' || c == ' ' || c == '\t')
+        .filter(|word| !word.is_empty())
+        .collect();
+
+    // Hyphen ranges, such as `1.2 - 2.3.4`, include both ends
+    if let [start, "-", end] = words.as_slice() {
+        return parse_comparator(Some(ReqOp::GreaterEq), start)
+            .into_iter()
+            .chain(parse_comparator(Some(ReqOp::LessEq), end))
+            .collect();
+    }
+
+    let mut result = vec![];
+    let mut words = words.into_iter();
+
+    while let Some(word) = words.next() {
+        let (op, version) = split_op(word);
+
+        // The version may be separated from its operator by whitespace
+        let version = match version {
+            "" => words.next().unwrap_or_default(),
+            version => version,
+        };
+
+        result.extend(parse_comparator(op, version));
+    }
+
+    result
+}
+
+/// Split the operator a comparator starts with, if any, from its version
+fn split_op(comparator: &str) -> (Option<ReqOp>, &str) {
+    use ReqOp::*;
+
+    for (prefix, op) in &[
+        ("<=", LessEq),
+        (">=", GreaterEq),
+        ("~>", Tilde),
+        ("<", Less),
+        (">", Greater),
+        ("=", Exact),
+        ("~", Tilde),
+        ("^", Caret),
+    ] {
+        if let Some(version) = comparator.strip_prefix(prefix) {
+            return (Some(*op), version);
+        }
+    }
+
+    (None, comparator)
+}
+
+/// Parse a comparator from its operator and (possibly partial) version.
+///
+/// Components after a wildcard are ignored, as in Cargo. A wildcard major
+/// version (e.g. `*` or `>= *`) doesn't restrict the range at all, unless it
+/// is an exclusive bound, which `semver-parser` reads as `< 0.0.0`. Build
+/// metadata doesn't affect which versions match, so it's left out.
+fn parse_comparator(op: Option<ReqOp>, version: &str) -> Option<ReqComparator> {
+    let version = version.split('+').next().unwrap_or_default();
+    let (numbers, pre) = match version.find('-') {
+        Some(i) => (&version[..i], &version[i + 1..]),
+        None => (version, ""),
+    };
+
+    let mut components = numbers.split('.').map(|component| component.parse().ok());
+    let major = components.next().flatten();
+    let minor = major.and(components.next().flatten());
+    let patch = minor.and(components.next().flatten());
+
+    let is_wildcard = numbers
+        .split('.')
+        .any(|component| matches!(component, "*" | "x" | "X"));
+    let op = match op {
+        Some(op) => op,
+        None if is_wildcard => ReqOp::Wildcard,
+        None => ReqOp::Caret,
+    };
+
+    match (major, op) {
+        (Some(major), _) => Some(ReqComparator {
+            op,
+            major,
+            minor,
+            patch,
+            pre: parse_identifiers(pre),
+        }),
+        (None, ReqOp::Less) | (None, ReqOp::Greater) => Some(ReqComparator {
+            op: ReqOp::Less,
+            major: 0,
+            minor: Some(0),
+            patch: Some(0),
+            pre: vec![],
+        }),
+        (None, _) => None,
+    }
+}
+
+/// Parse dot-separated pre-release identifiers, such as `beta.1`
+pub(super) fn parse_identifiers(pre: &str) -> Vec<Identifier> {
+    if pre.is_empty() {
+        return vec![];
+    }
+
+    pre.split('.')
+        .map(|identifier| match identifier.parse() {
+            Ok(n) => Identifier::Numeric(n),
+            Err(_) => Identifier::AlphaNumeric(identifier.to_owned()),
+        })
+        .collect()
+}
+
+/// Parse a version requirement into the comparators it consists of: one
+/// list per `||`-separated range, each matching the versions which satisfy
+/// all of its comparators.
+///
+/// Caret, tilde, wildcard and partial requirements are expanded into plain
+/// comparators the way Cargo interprets them, e.g. `1.2` becomes
+/// `>= 1.2.0, < 2.0.0`. Sides which are unbounded have no comparator, so
+/// `^0.1` becomes just `< 0.2.0`, and `*` has no comparators at all.
+pub fn parse_req_structure(req: &str) -> Result<Vec<Vec<Comparator>>, RangeError> {
+    let mut result = vec![];
+
+    for comparators in parse_comparators(req)? {
+        let mut primitive = vec![];
+
+        for comparator in &comparators {
+            let (start, end) = expand_comparator(comparator)?;
+
+            if comparator.is_exact() {
+                primitive.extend(start.version().map(|v| primitive_comparator(Op::Eq, v)));
+                continue;
+            }
+
+            match &start {
+                Bound::Unbounded => {}
+                Bound::Exclusive(v) => primitive.push(primitive_comparator(Op::Gt, v)),
+                Bound::Inclusive(v) => primitive.push(primitive_comparator(Op::Gte, v)),
+            }
+            match &end {
+                Bound::Unbounded => {}
+                Bound::Exclusive(v) => primitive.push(primitive_comparator(Op::Lt, v)),
+                Bound::Inclusive(v) => primitive.push(primitive_comparator(Op::Lte, v)),
+            }
+        }
+
+        result.push(primitive);
+    }
+
+    Ok(result)
+}
+
+/// An [`UnaffectedRange`] along with the requirement it was written as.
+///
+/// Converting a `VersionReq` normalizes the versions in it, e.g. `< 2.0`
+/// becomes `< 2.0.0`. Parsing requirements via [`AuthoredRange::parse`]
+/// instead retains the text as it was authored, e.g. for displaying it or
+/// diffing it against the advisory, while computing with the normalized
+/// range. The range is converted from the comparators as written, so `> 2.0`
+/// starts at `2.1.0`.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct AuthoredRange {
+    range: UnaffectedRange,
+    authored: String,
+}
+
+impl AuthoredRange {
+    /// Parse a version requirement into unaffected ranges, retaining the
+    /// authored text of the `||`-separated part each range comes from
+    pub fn parse(req: &str) -> Result<Vec<AuthoredRange>, RangeError> {
+        let mut result = vec![];
+
+        for part in req.split("||") {
+            let authored = part.trim();
+
+            let mut ranges = vec![];
+            for comparators in parse_comparators(authored)? {
+                ranges.push(
+                    single_range(&comparators).map_err(|e| RangeError::Requirement {
+                        req: authored.to_owned(),
+                        source: Box::new(e),
+                    })?,
+                );
+            }
+
+            result.extend(ranges.into_iter().map(|range| AuthoredRange {
+                range,
+                authored: authored.to_owned(),
+            }));
+        }
+
+        Ok(result)
+    }
+
+    /// Get the normalized range
+    pub fn range(&self) -> &UnaffectedRange {
+        &self.range
+    }
+
+    /// Get the requirement this range was parsed from, as it was written
+    pub fn authored(&self) -> &str {
+        &self.authored
+    }
+}
+
+/// Boundary of an [`UnaffectedRange`]
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub enum Bound {
+    /// All versions in this direction are included
+    Unbounded,
+
+    /// The given version is excluded from the range
+    Exclusive(Version),
+
+    /// The given version is included in the range
+    Inclusive(Version),
+}
+
+impl Bound {
+    /// Get the version this bound is placed at, if any
+    pub fn version(&self) -> Option<&Version> {
+        match self {
+            Bound::Unbounded => None,
+            Bound::Exclusive(v) | Bound::Inclusive(v) => Some(v),
+        }
+    }
+
+    /// Get the bound on the opposite side of the same version, which includes
+    /// it if and only if this one doesn't, e.g. to turn the start of a range
+    /// into the end of the one directly below it
+    fn flipped(&self) -> Bound {
+        match self {
+            Bound::Unbounded => Bound::Unbounded,
+            Bound::Exclusive(v) => Bound::Inclusive(v.clone()),
+            Bound::Inclusive(v) => Bound::Exclusive(v.clone()),
+        }
+    }
+
+    /// Strip build metadata from the version this bound is placed at
+    fn without_build(self) -> Bound {
+        match self {
+            Bound::Unbounded => Bound::Unbounded,
+            Bound::Exclusive(v) => Bound::Exclusive(without_build(v)),
+            Bound::Inclusive(v) => Bound::Inclusive(without_build(v)),
+        }
+    }
+}
+
+/// A [`Bound`] used as the start of a range, ordered by how low it lets the
+/// range start.
+///
+/// `Unbounded` sorts below all versions, and at equal versions an inclusive
+/// bound sorts before an exclusive one.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) struct StartBound<'a>(pub &'a Bound);
+
+impl Ord for StartBound<'_> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self.0, other.0) {
+            (Bound::Unbounded, Bound::Unbounded) => Ordering::Equal,
+            (Bound::Unbounded, _) => Ordering::Less,
+            (_, Bound::Unbounded) => Ordering::Greater,
+            (Bound::Inclusive(a), Bound::Exclusive(b)) if a == b => Ordering::Less,
+            (Bound::Exclusive(a), Bound::Inclusive(b)) if a == b => Ordering::Greater,
+            (a, b) => a.version().cmp(&b.version()),
+        }
+    }
+}
+
+impl PartialOrd for StartBound<'_> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl fmt::Display for StartBound<'_> {
+    /// Render the bound as a comparator, e.g. `>=1.0.0`, or `*` if unbounded
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            Bound::Unbounded => write!(f, "*"),
+            Bound::Exclusive(v) => write!(f, ">{}", v),
+            Bound::Inclusive(v) => write!(f, ">={}", v),
+        }
+    }
+}
+
+/// A [`Bound`] used as the end of a range, ordered by how high it lets the
+/// range end.
+///
+/// `Unbounded` sorts above all versions, and at equal versions an exclusive
+/// bound sorts before an inclusive one.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) struct EndBound<'a>(pub &'a Bound);
+
+impl Ord for EndBound<'_> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self.0, other.0) {
+            (Bound::Unbounded, Bound::Unbounded) => Ordering::Equal,
+            (Bound::Unbounded, _) => Ordering::Greater,
+            (_, Bound::Unbounded) => Ordering::Less,
+            (Bound::Exclusive(a), Bound::Inclusive(b)) if a == b => Ordering::Less,
+            (Bound::Inclusive(a), Bound::Exclusive(b)) if a == b => Ordering::Greater,
+            (a, b) => a.version().cmp(&b.version()),
+        }
+    }
+}
+
+impl PartialOrd for EndBound<'_> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl fmt::Display for EndBound<'_> {
+    /// Render the bound as a comparator, e.g. `<2.0.0`, or `*` if unbounded
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            Bound::Unbounded => write!(f, "*"),
+            Bound::Exclusive(v) => write!(f, "<{}", v),
+            Bound::Inclusive(v) => write!(f, "<={}", v),
+        }
+    }
+}
+
+/// Does a range ending at `end` directly border on one starting at `start`,
+/// without leaving any versions in between?
+fn touches(end: &Bound, start: &Bound) -> bool {
+    match (end, start) {
+        (Bound::Exclusive(end), Bound::Inclusive(start))
+        | (Bound::Inclusive(end), Bound::Exclusive(start)) => end == start,
+        _ => false,
+    }
+}
+
+/// Is this the lowest version there is, i.e. `0.0.0-0`?
+fn is_lowest_version(version: &Version) -> bool {
+    version.major == 0
+        && version.minor == 0
+        && version.patch == 0
+        && version.pre == [Identifier::Numeric(0)]
+}
+
+/// Is this `0.0.0` or the lowest version there is, `0.0.0-0`?
+fn is_lowest_release(version: &Version) -> bool {
+    is_lowest_version(version) || *version == Version::new(0, 0, 0)
+}
+
+/// Is there at least one version at or above `start` which is also at or
+/// below `end`?
+fn precedes(start: &Bound, end: &Bound) -> bool {
+    match (start, end) {
+        // Nothing lies below `0.0.0-0`, the lowest possible version, and
+        // nothing is meant to lie below `0.0.0` either (see `is_empty`)
+        (_, Bound::Exclusive(end)) if is_lowest_release(end) => false,
+        (Bound::Unbounded, _) | (_, Bound::Unbounded) => true,
+        (Bound::Inclusive(start), Bound::Inclusive(end)) => start <= end,
+        (Bound::Inclusive(start), Bound::Exclusive(end))
+        | (Bound::Exclusive(start), Bound::Inclusive(end)) => start < end,
+        // There's no version between e.g. `1.0.0` and `1.0.1-0`
+        (Bound::Exclusive(start), Bound::Exclusive(end)) => increment(start) < *end,
+    }
+}
+
+/// Convert the version a `semver-parser` comparator refers to into a
+/// `semver::Version`, retaining any pre-release identifiers
+fn comparator_version(comparator: &Comparator) -> Version {
+    Version {
+        major: comparator.major,
+        minor: comparator.minor,
+        patch: comparator.patch,
+        pre: comparator
+            .pre
+            .iter()
+            .map(|identifier| match identifier {
+                semver_parser::Identifier::Numeric(n) => Identifier::Numeric(*n),
+                semver_parser::Identifier::AlphaNumeric(s) => Identifier::AlphaNumeric(s.clone()),
+            })
+            .collect(),
+        build: vec![],
+    }
+}
+
+/// Create a `semver-parser` comparator for the given version
+fn primitive_comparator(op: Op, version: &Version) -> Comparator {
+    Comparator {
+        op,
+        major: version.major,
+        minor: version.minor,
+        patch: version.patch,
+        pre: version
+            .pre
+            .iter()
+            .map(|identifier| match identifier {
+                Identifier::Numeric(n) => semver_parser::Identifier::Numeric(*n),
+                Identifier::AlphaNumeric(s) => semver_parser::Identifier::AlphaNumeric(s.clone()),
+            })
+            .collect(),
+    }
+}
+
+/// Formats a list of ranges by their `Display` impls, for logging
+#[cfg(feature = "tracing")]
+pub(super) struct DisplayList<'a, T>(pub(super) &'a [T]);
+
+#[cfg(feature = "tracing")]
+impl<T: fmt::Display> fmt::Debug for DisplayList<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[")?;
+
+        for (i, item) in self.0.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+
+            write!(f, "{}", item)?;
+        }
+
+        write!(f, "]")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        deserialize_ranges, expand_comparator, parse_comparators, parse_req_structure,
+        ranges_from_version_req, single_range, AuthoredRange, Bound, EndBound, RangeError,
+        RangeOrigin, ReqComparator, StartBound, StrictConversion, UnaffectedRange,
+    };
+    use crate::osv::affected_ranges;
+    use proptest::prelude::*;
+    use semver::{Identifier, Version, VersionReq};
+    use semver_parser::{Comparator, Compat, Op, Range, RangeSet};
+    use serde::Deserialize;
+    use std::convert::TryFrom;
+
+    /// Parse a requirement consisting of a single `semver-parser` range
+    fn parse_semver_range(input: &str) -> Range {
+        let mut range_set = RangeSet::parse(input, Compat::Cargo).unwrap();
+        assert_eq!(range_set.ranges.len(), 1);
+        range_set.ranges.remove(0)
+    }
+
+    /// Parse a requirement consisting of a single range
+    fn parse_range(input: &str) -> UnaffectedRange {
+        try_parse_range(input).unwrap()
+    }
+
+    /// Attempt to parse a requirement consisting of a single range
+    fn try_parse_range(input: &str) -> Result<UnaffectedRange, RangeError> {
+        input.parse()
+    }
+
+    #[test]
+    fn exact_version() {
+        let version = Version::new(1, 0, 0);
+        let range = parse_range("=1.0.0");
+        assert_eq!(range.start, Bound::Inclusive(version.clone()));
+        assert_eq!(range.end, Bound::Inclusive(version));
+        assert!(range.is_valid());
+    }
+
+    #[test]
+    fn from_version_req() {
+        let convert = |req| UnaffectedRange::from_version_req(&VersionReq::parse(req).unwrap());
+
+        assert_eq!(convert(">= 1.2.0").unwrap(), vec![parse_range(">= 1.2.0")]);
+        assert_eq!(
+            convert(">= 1.2.0, < 1.5.0").unwrap(),
+            vec![UnaffectedRange::new(
+                Bound::Inclusive(Version::new(1, 2, 0)),
+                Bound::Exclusive(Version::new(1, 5, 0))
+            )
+            .unwrap()]
+        );
+        assert_eq!(
+            convert("< 1.0.0 || >= 2.0.0").unwrap(),
+            vec![parse_range("< 1.0.0"), parse_range(">= 2.0.0")]
+        );
+        assert!(convert(">= 1.0.0, =1.2.0").is_err());
+    }
+
+    #[test]
+    fn exact_zero_version() {
+        let version = Version::new(0, 0, 0);
+        let range = parse_range("=0.0.0");
+        assert_eq!(range.start, Bound::Inclusive(version.clone()));
+        assert_eq!(range.end, Bound::Inclusive(version));
+    }
+
+    #[test]
+    fn exact_version_with_other_comparator() {
+        for input in &[">= 0.5.0, =1.0.0", "=1.0.0, < 2.0.0", "=1.0.0, =1.0.0"] {
+            assert_eq!(
+                try_parse_range(input),
+                Err(RangeError::ExactVersionWithOtherPredicates)
+            );
+        }
+
+        for input in &[">=1.0, =1.2.3", ">= 1.0.0, < 1.5.0, = 1.2.3"] {
+            let req = VersionReq::parse(input).unwrap();
+            assert_eq!(
+                ranges_from_version_req(&req),
+                Err(RangeError::ExactVersionWithOtherPredicates.in_requirement(&req))
+            );
+        }
+
+        // An exact version on its own, or in a range of its own, is fine
+        let req = VersionReq::parse("=1.2.3").unwrap();
+        assert_eq!(
+            ranges_from_version_req(&req).unwrap(),
+            vec![parse_range("=1.2.3")]
+        );
+        let req = VersionReq::parse(">= 1.0.0, < 1.5.0 || >= 2.0.0 || =1.7.0").unwrap();
+        assert_eq!(ranges_from_version_req(&req).unwrap().len(), 3);
+    }
+
+    #[test]
+    fn too_many_predicates() {
+        assert_eq!(
+            try_parse_range(">= 1.0.0, < 2.0.0, < 3.0.0"),
+            Err(RangeError::TooManyPredicates)
+        );
+    }
+
+    #[test]
+    fn multiple_lower_bounds() {
+        assert_eq!(
+            try_parse_range(">= 1.0.0, > 1.5.0"),
+            Err(RangeError::MultipleLowerBounds)
+        );
+    }
+
+    #[test]
+    fn multiple_upper_bounds() {
+        assert_eq!(
+            try_parse_range("< 2.0.0, <= 1.5.0"),
+            Err(RangeError::MultipleUpperBounds)
+        );
+    }
+
+    #[test]
+    fn invalid_range() {
+        assert_eq!(
+            try_parse_range(">= 2.0.0, < 1.0.0"),
+            Err(RangeError::InvalidRange)
+        );
+    }
+
+    #[test]
+    fn new_from_bounds() {
+        let v1 = Version::new(1, 0, 0);
+        let v2 = Version::new(2, 0, 0);
+
+        let range =
+            UnaffectedRange::new(Bound::Exclusive(v1.clone()), Bound::Inclusive(v2.clone()))
+                .unwrap();
+        assert_eq!(range, parse_range("> 1.0.0, <= 2.0.0"));
+
+        assert_eq!(
+            UnaffectedRange::new(Bound::Inclusive(v2), Bound::Exclusive(v1)),
+            Err(RangeError::InvalidRange)
+        );
+    }
+
+    #[test]
+    fn version_req_with_more_than_two_predicates() {
+        // `semver` requires versions to match every comparator of a range, so
+        // these don't describe several disjoint ranges
+        for input in &[
+            ">= 1.0.0, < 1.5.0, >= 2.0.0",
+            "< 2.5.0, >= 2.0.0, <= 1.5.0, > 1.0.0",
+            "< 1.0.0, >= 2.0.0, < 3.0.0",
+        ] {
+            let req = VersionReq::parse(input).unwrap();
+            for version in &["0.5.0", "1.2.0", "2.2.0", "3.5.0"] {
+                assert!(!req.matches(&Version::parse(version).unwrap()));
+            }
+            assert_eq!(
+                ranges_from_version_req(&req),
+                Err(RangeError::TooManyPredicates.in_requirement(&req)),
+                "{}",
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn version_req_with_multiple_ranges() {
+        let req = VersionReq::parse(">= 1.0.0, < 1.5.0 || >= 2.0.0").unwrap();
+        assert_eq!(
+            ranges_from_version_req(&req).unwrap(),
+            vec![parse_range(">= 1.0.0, < 1.5.0"), parse_range(">= 2.0.0")]
+        );
+
+        let req = VersionReq::parse(">=1.0, <1.5 || >=2.0").unwrap();
+        let ranges = ranges_from_version_req(&req).unwrap();
+        assert_eq!(ranges.len(), 2);
+        assert!(!ranges[0].overlaps(&ranges[1]));
+
+        let req = VersionReq::parse(">= 1.0.0, < 1.5.0").unwrap();
+        assert_eq!(
+            ranges_from_version_req(&req).unwrap(),
+            vec![parse_range(">= 1.0.0, < 1.5.0")]
+        );
+    }
+
+    #[test]
+    fn bound_ordering() {
+        let v1 = Version::new(1, 0, 0);
+        let v2 = Version::new(2, 0, 0);
+        let mut bounds = vec![
+            Bound::Inclusive(v2.clone()),
+            Bound::Exclusive(v1.clone()),
+            Bound::Unbounded,
+            Bound::Exclusive(v2.clone()),
+            Bound::Inclusive(v1.clone()),
+        ];
+
+        bounds.sort_by(|a, b| StartBound(a).cmp(&StartBound(b)));
+        assert_eq!(
+            bounds,
+            vec![
+                Bound::Unbounded,
+                Bound::Inclusive(v1.clone()),
+                Bound::Exclusive(v1.clone()),
+                Bound::Inclusive(v2.clone()),
+                Bound::Exclusive(v2.clone()),
+            ]
+        );
+
+        bounds.sort_by(|a, b| EndBound(a).cmp(&EndBound(b)));
+        assert_eq!(
+            bounds,
+            vec![
+                Bound::Exclusive(v1.clone()),
+                Bound::Inclusive(v1),
+                Bound::Exclusive(v2.clone()),
+                Bound::Inclusive(v2),
+                Bound::Unbounded,
+            ]
+        );
+    }
+
+    #[test]
+    fn prerelease_bounds() {
+        let range = parse_range(">= 1.0.0-alpha, < 1.0.0");
+        assert_eq!(
+            range.start,
+            Bound::Inclusive(Version::parse("1.0.0-alpha").unwrap())
+        );
+        assert_eq!(range.end, Bound::Exclusive(Version::new(1, 0, 0)));
+
+        // Every pre-release identifier is kept, also when going through
+        // `VersionReq`
+        let req = VersionReq::parse(">= 1.0.0-beta.2").unwrap();
+        let range = ranges_from_version_req(&req).unwrap().remove(0);
+        let version = range.start().version().unwrap();
+        assert_eq!(version, &Version::parse("1.0.0-beta.2").unwrap());
+        assert_eq!(
+            version.pre,
+            vec![
+                Identifier::AlphaNumeric("beta".to_owned()),
+                Identifier::Numeric(2)
+            ]
+        );
+    }
+
+    #[test]
+    fn merge_with() {
+        let mut range = parse_range(">= 1.0.0, < 2.0.0");
+        assert!(range.merge_with(&parse_range(">= 1.5.0, < 3.0.0")));
+        assert_eq!(range, parse_range(">= 1.0.0, < 3.0.0"));
+
+        // Adjacent ranges, extending the start
+        assert!(range.merge_with(&parse_range("< 1.0.0").with_origin(RangeOrigin::Unaffected)));
+        assert_eq!(
+            range,
+            parse_range("< 3.0.0").with_origin(RangeOrigin::Unaffected)
+        );
+
+        // Contained ranges don't change anything, except for the origin
+        assert!(range.merge_with(&parse_range("< 2.0.0")));
+        assert_eq!(range, parse_range("< 3.0.0"));
+
+        // Ranges with versions in between are left alone
+        let mut range = parse_range("< 1.0.0");
+        for other in &["> 1.0.0", ">= 1.5.0, < 2.0.0", ">= 0.5.0, < 0.5.0"] {
+            assert!(!range.merge_with(&parse_range(other)), "{}", other);
+            assert_eq!(range, parse_range("< 1.0.0"));
+        }
+    }
+
+    #[test]
+    fn same_versions_ignoring_inclusivity() {
+        let a = parse_range(">= 1.0.0, < 2.0.0");
+        let b = parse_range("> 1.0.0, <= 2.0.0");
+        assert_ne!(a, b);
+        assert!(a.same_versions_ignoring_inclusivity(&b));
+        assert!(b.same_versions_ignoring_inclusivity(&a));
+
+        let unaffected = a.clone().with_origin(RangeOrigin::Unaffected);
+        assert!(a.same_versions_ignoring_inclusivity(&unaffected));
+
+        assert!(!a.same_versions_ignoring_inclusivity(&parse_range(">= 1.0.0, < 3.0.0")));
+        assert!(!parse_range(">= 1.0.0").same_versions_ignoring_inclusivity(&a));
+        assert!(!parse_range("< 2.0.0").same_versions_ignoring_inclusivity(&a));
+    }
+
+    #[test]
+    fn intersection_disjoint() {
+        let a = parse_range(">= 1.0.0, < 2.0.0");
+        let b = parse_range(">= 3.0.0");
+        assert_eq!(a.intersection(&b), None);
+        assert_eq!(b.intersection(&a), None);
+    }
+
+    #[test]
+    fn intersection_touching() {
+        let a = parse_range("<= 1.0.0");
+        let b = parse_range(">= 1.0.0");
+        assert_eq!(a.intersection(&b), Some(parse_range("=1.0.0")));
+
+        let c = parse_range("< 1.0.0");
+        assert_eq!(c.intersection(&b), None);
+    }
+
+    #[test]
+    fn intersection_nested() {
+        let outer = parse_range(">= 1.0.0, < 3.0.0");
+        let inner = parse_range("> 1.0.0, <= 2.0.0");
+        assert_eq!(outer.intersection(&inner), Some(inner.clone()));
+        assert_eq!(inner.intersection(&outer), Some(inner));
+    }
+
+    #[test]
+    fn intersection_partial() {
+        let a = parse_range(">= 1.0.0, <= 2.0.0");
+        let b = parse_range("> 1.5.0, < 2.0.0");
+        let c = parse_range(">= 1.5.0");
+        assert_eq!(a.intersection(&b), Some(b.clone()));
+        assert_eq!(a.intersection(&c), Some(parse_range(">= 1.5.0, <= 2.0.0")));
+    }
+
+    #[test]
+    fn empty_ranges() {
+        for input in &["> 1.0.0, < 1.0.0", ">= 1.0.0, < 1.0.0", "> 1.0.0, <= 1.0.0"] {
+            let range = parse_range(input);
+            assert!(range.is_valid());
+            assert!(range.is_empty(), "{} should be empty", input);
+            assert!(!range.overlaps(&parse_range(">= 0.5.0, < 2.0.0")));
+            assert!(!range.overlaps(&UnaffectedRange::default()));
+        }
+
+        assert!(!parse_range(">= 1.0.0, <= 1.0.0").is_empty());
+        assert!(!parse_range("=1.0.0").is_empty());
+        assert!(!UnaffectedRange::default().is_empty());
+
+        // Nothing is below the lowest version, and the pre-releases of
+        // `0.0.0` don't count for `< 0.0.0` either
+        for input in &["< 0.0.0-0", "< 0.0.0"] {
+            let range = parse_range(input);
+            assert!(range.is_empty(), "{} should be empty", input);
+            assert!(!range.overlaps(&UnaffectedRange::default()));
+            assert!(!range.contains(&Version::parse("0.0.0-alpha").unwrap()));
+            assert_eq!(range.canonical_form(), "<0.0.0-0");
+            assert!(UnaffectedRange::default().contains_range(&range));
+        }
+        assert!(!parse_range("<= 0.0.0").is_empty());
+
+        // Whatever the range starts at
+        for input in &[">= 0.0.0-alpha, < 0.0.0", ">= 0.0.0-0, < 0.0.0"] {
+            let range = parse_range(input);
+            assert!(range.is_valid());
+            assert!(range.is_empty(), "{} should be empty", input);
+            assert!(!range.contains(&Version::parse("0.0.0-beta").unwrap()));
+            assert_eq!(range.canonical_form(), "<0.0.0-0");
+        }
+
+        // No version lies between adjacent exclusive bounds
+        let range = parse_range("> 1.0.0, < 1.0.1-0");
+        assert!(range.is_empty());
+        assert!(!range.overlaps(&UnaffectedRange::default()));
+        assert!(!parse_range("> 1.0.0, < 1.0.1-1").is_empty());
+    }
+
+    #[test]
+    fn strict_conversion() {
+        let strict = |input| UnaffectedRange::try_from(StrictConversion(input));
+
+        for input in &[
+            ">= 1.0.0",
+            "< 2.0.0",
+            "*",
+            "^1.2.3",
+            "~1.2",
+            "1.*",
+            "1.2.3",
+            "=1.2",
+            "^0.0",
+            ">= 0.0.0, < 1.0.0",
+        ] {
+            assert!(try_parse_range(input).is_ok(), "{}", input);
+            assert_eq!(strict(input), Err(RangeError::ImplicitBound), "{}", input);
+        }
+
+        for input in &[
+            ">= 1.0.0, < 2.0.0",
+            "> 1.0.0, <= 1.5",
+            "=1.2.3",
+            "=1.2.3-beta.1",
+        ] {
+            assert_eq!(strict(input), try_parse_range(input), "{}", input);
+        }
+
+        // Other errors take precedence
+        assert_eq!(
+            strict(">= 1.0.0, >= 2.0.0"),
+            Err(RangeError::MultipleLowerBounds)
+        );
+        assert_eq!(strict("^1.2 || ^2"), Err(RangeError::MultipleRanges));
+    }
+
+    #[test]
+    fn empty_comparator_set() {
+        let range = Range {
+            comparator_set: vec![],
+            compat: Compat::Cargo,
+        };
+        assert_eq!(
+            UnaffectedRange::try_from(range).unwrap(),
+            UnaffectedRange::default()
+        );
+    }
+
+    #[test]
+    fn req_structure() {
+        let comparator = |op, major| Comparator {
+            op,
+            major,
+            minor: 0,
+            patch: 0,
+            pre: vec![],
+        };
+
+        assert_eq!(
+            parse_req_structure(">=1, <2 || >=3").unwrap(),
+            vec![
+                vec![comparator(Op::Gte, 1), comparator(Op::Lt, 2)],
+                vec![comparator(Op::Gte, 3)],
+            ]
+        );
+        assert_eq!(
+            parse_req_structure("^1").unwrap(),
+            vec![vec![comparator(Op::Gte, 1), comparator(Op::Lt, 2)]]
+        );
+        assert_eq!(
+            parse_req_structure("> 1 || =3.0.0").unwrap(),
+            vec![vec![comparator(Op::Gte, 2)], vec![comparator(Op::Eq, 3)]]
+        );
+        assert_eq!(
+            parse_req_structure("^0.0 || *").unwrap(),
+            vec![
+                vec![Comparator {
+                    op: Op::Lt,
+                    major: 0,
+                    minor: 1,
+                    patch: 0,
+                    pre: vec![]
+                }],
+                vec![]
+            ]
+        );
+        assert!(matches!(
+            parse_req_structure(">= 1.0.0 <"),
+            Err(RangeError::InvalidRequirement(_))
+        ));
+    }
+
+    #[test]
+    fn wildcard() {
+        assert_eq!(parse_range("*"), UnaffectedRange::default());
+        assert_eq!(parse_range(">= 0.0.0"), UnaffectedRange::default());
+
+        let req = VersionReq::parse("*").unwrap();
+        assert_eq!(
+            ranges_from_version_req(&req).unwrap(),
+            vec![UnaffectedRange::default()]
+        );
+        assert_eq!(
+            ranges_from_version_req(&VersionReq::any()).unwrap(),
+            vec![UnaffectedRange::default()]
+        );
+    }
+
+    #[test]
+    fn display() {
+        let v1 = Version::new(1, 0, 0);
+
+        assert_eq!(
+            StartBound(&Bound::Inclusive(v1.clone())).to_string(),
+            ">=1.0.0"
+        );
+        assert_eq!(
+            StartBound(&Bound::Exclusive(v1.clone())).to_string(),
+            ">1.0.0"
+        );
+        assert_eq!(
+            EndBound(&Bound::Inclusive(v1.clone())).to_string(),
+            "<=1.0.0"
+        );
+        assert_eq!(EndBound(&Bound::Exclusive(v1)).to_string(), "<1.0.0");
+        assert_eq!(StartBound(&Bound::Unbounded).to_string(), "*");
+
+        for (input, expected) in &[
+            (">= 1.0.0, < 2.0.0", ">=1.0.0, <2.0.0"),
+            ("> 1.0.0, <= 2.0.0", ">1.0.0, <=2.0.0"),
+            ("< 1.5.0", "<1.5.0"),
+            ("> 1.5.0", ">1.5.0"),
+            ("= 1.2.3", "=1.2.3"),
+            ("*", "*"),
+        ] {
+            assert_eq!(&parse_range(input).to_string(), expected);
+        }
+    }
+
+    #[test]
+    fn build_metadata() {
+        let build_a = Version::parse("1.0.0+a").unwrap();
+        let build_b = Version::parse("1.0.0+b").unwrap();
+
+        let exact = UnaffectedRange::new(
+            Bound::Inclusive(build_a.clone()),
+            Bound::Inclusive(build_b.clone()),
+        )
+        .unwrap();
+        assert!(exact.is_valid());
+        assert!(!exact.is_empty());
+        assert_eq!(exact.start(), &Bound::Inclusive(Version::new(1, 0, 0)));
+        assert!(exact.start().version().unwrap().build.is_empty());
+        assert_eq!(exact.to_string(), "=1.0.0");
+
+        let below = UnaffectedRange::new(Bound::Unbounded, Bound::Exclusive(build_a)).unwrap();
+        let above = UnaffectedRange::new(Bound::Inclusive(build_b), Bound::Unbounded).unwrap();
+        assert!(!below.overlaps(&above));
+        assert!(exact.overlaps(&above));
+        assert!(!exact.overlaps(&below));
+    }
+
+    #[test]
+    fn contains() {
+        let range = parse_range("> 1.0.0, <= 2.0.0");
+        assert!(!range.contains(&Version::new(1, 0, 0)));
+        assert!(range.contains(&Version::new(1, 0, 1)));
+        assert!(range.contains(&Version::new(2, 0, 0)));
+        assert!(!range.contains(&Version::new(2, 0, 1)));
+
+        // Pre-releases are regular points on the version line
+        assert!(range.contains(&Version::parse("2.0.0-rc.1").unwrap()));
+        assert!(!range.contains(&Version::parse("1.0.0-rc.1").unwrap()));
+
+        assert!(parse_range("*").contains(&Version::new(0, 0, 0)));
+        assert!(!parse_range("> 1.0.0, < 1.0.0").contains(&Version::new(1, 0, 0)));
+    }
+
+    #[test]
+    fn caret_and_tilde() {
+        for (input, expected) in &[
+            ("^0.1.2", ">=0.1.2, <0.2.0"),
+            ("^0.0.3", ">=0.0.3, <0.0.4"),
+            // `>= 0.0.0` covers everything below, like a wildcard does
+            ("^0.0", "<0.1.0"),
+            ("^1.2.3", ">=1.2.3, <2.0.0"),
+            ("~1.2", ">=1.2.0, <1.3.0"),
+            ("~1.2.3", ">=1.2.3, <1.3.0"),
+            ("~0.1.2", ">=0.1.2, <0.2.0"),
+            // No operator means caret
+            ("1.2.3", ">=1.2.3, <2.0.0"),
+            ("0.2.3", ">=0.2.3, <0.3.0"),
+            ("0.0.3", ">=0.0.3, <0.0.4"),
+        ] {
+            assert_eq!(&parse_range(input).to_string(), expected, "{}", input);
+
+            let req = VersionReq::parse(input).unwrap();
+            let ranges = ranges_from_version_req(&req).unwrap();
+            assert_eq!(ranges, vec![parse_range(input)], "{}", input);
+        }
+    }
+
+    #[test]
+    fn predicate_order() {
+        for (reversed, ordered) in &[
+            ("< 2.0.0, >= 1.0.0", ">= 1.0.0, < 2.0.0"),
+            ("<= 2.0.0, > 1.0.0", "> 1.0.0, <= 2.0.0"),
+            (
+                "< 1.0.0-beta, >= 1.0.0-alpha",
+                ">= 1.0.0-alpha, < 1.0.0-beta",
+            ),
+        ] {
+            let range = UnaffectedRange::try_from(parse_semver_range(reversed)).unwrap();
+            assert_eq!(range, parse_range(ordered), "{}", reversed);
+
+            let req = VersionReq::parse(reversed).unwrap();
+            assert_eq!(
+                ranges_from_version_req(&req).unwrap(),
+                vec![parse_range(ordered)],
+                "{}",
+                reversed
+            );
+        }
+    }
+
+    #[test]
+    fn canonical_form() {
+        for (a, b, expected) in &[
+            ("^1.0.0", ">= 1.0.0, < 2.0.0", ">=1.0.0, <2.0.0"),
+            ("~1.2", ">= 1.2.0, < 1.3.0", ">=1.2.0, <1.3.0"),
+            ("= 1.0.0", ">= 1.0.0, <= 1.0.0", ">=1.0.0, <1.0.1-0"),
+            ("> 1.0.0", ">= 1.0.1-0", ">=1.0.1-0"),
+            ("<= 2.0", "< 2.1.0", "<2.1.0"),
+            ("<= 2.0.0", "< 2.0.1-0", "<2.0.1-0"),
+            ("*", ">= 0.0.0", "*"),
+            (">= 1.0.0, < 1.0.0", "> 2.0.0, < 2.0.1-0", "<0.0.0-0"),
+        ] {
+            assert_eq!(&parse_range(a).canonical_form(), expected, "{}", a);
+            assert_eq!(&parse_range(b).canonical_form(), expected, "{}", b);
+        }
+
+        assert_ne!(
+            parse_range("< 1.0.0").canonical_form(),
+            parse_range("<= 1.0.0").canonical_form()
+        );
+    }
+
+    #[test]
+    fn is_adjacent() {
+        let below = parse_range("< 1.0.0");
+
+        assert!(below.is_adjacent(&parse_range("= 1.0.0")));
+        assert!(parse_range("= 1.0.0").is_adjacent(&below));
+        assert!(below.is_adjacent(&parse_range(">= 1.0.0")));
+        assert!(parse_range("<= 1.0.0").is_adjacent(&parse_range("> 1.0.0, < 2.0.0")));
+
+        // Overlapping ranges aren't adjacent
+        assert!(!parse_range("<= 1.0.0").is_adjacent(&parse_range(">= 1.0.0")));
+        assert!(!below.is_adjacent(&below));
+
+        // A version lies between these
+        assert!(!below.is_adjacent(&parse_range("> 1.0.0")));
+        assert!(!below.is_adjacent(&parse_range(">= 1.0.1")));
+
+        // Empty ranges never are
+        assert!(!below.is_adjacent(&parse_range(">= 1.0.0, < 1.0.0")));
+    }
+
+    #[test]
+    fn contains_range() {
+        let outer = parse_range(">= 1.0.0, < 2.0.0");
+
+        // nested
+        assert!(outer.contains_range(&parse_range(">= 1.2.0, <= 1.5.0")));
+        assert!(outer.contains_range(&parse_range("> 1.0.0, < 2.0.0")));
+        assert!(!parse_range(">= 1.2.0, <= 1.5.0").contains_range(&outer));
+        assert!(parse_range("*").contains_range(&outer));
+        assert!(parse_range("< 2.0.0").contains_range(&outer));
+        assert!(!outer.contains_range(&parse_range("< 2.0.0")));
+
+        // equal
+        assert!(outer.contains_range(&outer));
+        assert!(parse_range("*").contains_range(&parse_range("*")));
+
+        // partially overlapping, including at inclusive/exclusive bounds
+        assert!(!outer.contains_range(&parse_range(">= 1.5.0, < 2.5.0")));
+        assert!(!outer.contains_range(&parse_range(">= 1.0.0, <= 2.0.0")));
+        assert!(!parse_range("> 1.0.0, < 2.0.0").contains_range(&outer));
+
+        // disjoint
+        assert!(!outer.contains_range(&parse_range(">= 2.0.0")));
+        assert!(!outer.contains_range(&parse_range("< 1.0.0")));
+
+        // empty
+        assert!(outer.contains_range(&parse_range("> 5.0.0, < 5.0.0")));
+    }
+
+    #[test]
+    fn bare_major_upper_bound() {
+        let range = parse_range("< 2");
+        assert_eq!(range, parse_range("< 2.0.0"));
+        assert_eq!(range.end(), &Bound::Exclusive(Version::new(2, 0, 0)));
+
+        assert!(range.contains(&Version::parse("1.99.0").unwrap()));
+        assert!(!range.contains(&Version::new(2, 0, 0)));
+        assert!(!range.contains(&Version::new(2, 5, 0)));
+
+        // By precedence, pre-releases of `2.0.0` lie below it
+        let alpha = Version::parse("2.0.0-alpha").unwrap();
+        assert!(range.contains(&alpha));
+        assert!(!parse_range(">= 2").contains(&alpha));
+
+        // Converting a `VersionReq` results in the same range
+        let req = VersionReq::parse("< 2").unwrap();
+        assert_eq!(ranges_from_version_req(&req).unwrap(), vec![range]);
+    }
+
+    #[test]
+    fn partial_versions() {
+        // Missing components are filled with zeros, except where the operator
+        // implies otherwise (`<= 2.0` covers all of `2.0.x`), in the same way
+        // Cargo interprets them. `VersionReq::matches` agrees with the
+        // equivalent requirement given for each.
+        for (input, equivalent, start, end) in &[
+            (
+                ">= 1",
+                ">= 1",
+                Bound::Inclusive(Version::new(1, 0, 0)),
+                Bound::Unbounded,
+            ),
+            (
+                "< 2.0",
+                "< 2.0",
+                Bound::Unbounded,
+                Bound::Exclusive(Version::new(2, 0, 0)),
+            ),
+            // `semver` itself reads this as `> 1.2.0`
+            (
+                "> 1.2",
+                ">= 1.3.0",
+                Bound::Inclusive(Version::new(1, 3, 0)),
+                Bound::Unbounded,
+            ),
+            (
+                "<= 2.0",
+                "<= 2.0",
+                Bound::Unbounded,
+                Bound::Exclusive(Version::new(2, 1, 0)),
+            ),
+            (
+                "= 1",
+                "= 1",
+                Bound::Inclusive(Version::new(1, 0, 0)),
+                Bound::Exclusive(Version::new(2, 0, 0)),
+            ),
+            (
+                ">= 1, < 2.0",
+                ">= 1, < 2.0",
+                Bound::Inclusive(Version::new(1, 0, 0)),
+                Bound::Exclusive(Version::new(2, 0, 0)),
+            ),
+        ] {
+            let range = parse_range(input);
+            assert_eq!(range.start(), start, "{}", input);
+            assert_eq!(range.end(), end, "{}", input);
+
+            let req = VersionReq::parse(equivalent).unwrap();
+            for version in &[
+                "0.9.9", "1.0.0", "1.2.0", "1.2.1", "2.0.0", "2.0.5", "2.1.0",
+            ] {
+                let version = Version::parse(version).unwrap();
+                assert_eq!(
+                    range.contains(&version),
+                    req.matches(&version),
+                    "{} in {}",
+                    version,
+                    input
+                );
+            }
+        }
+
+        // A `VersionReq` has already lost the partial version
+        let req = VersionReq::parse("> 1.2").unwrap();
+        assert_eq!(
+            ranges_from_version_req(&req).unwrap(),
+            vec![parse_range("> 1.2.0")]
+        );
+    }
+
+    #[test]
+    fn comparator_expansion() {
+        use super::ReqOp::*;
+
+        fn expand(comparator: &ReqComparator) -> Result<String, RangeError> {
+            expand_comparator(comparator)
+                .map(|(start, end)| format!("{}, {}", StartBound(&start), EndBound(&end)))
+        }
+
+        // Each comparator, and the bounds Cargo interprets it as
+        let table = [
+            ("=1.2.3", ">=1.2.3, <=1.2.3"),
+            ("=1.2.3-beta.1", ">=1.2.3-beta.1, <=1.2.3-beta.1"),
+            ("=0.0.0", ">=0.0.0, <=0.0.0"),
+            ("=1.2", ">=1.2.0, <1.3.0"),
+            ("=1", ">=1.0.0, <2.0.0"),
+            ("=0.0", "*, <0.1.0"),
+            (">1.2.3", ">1.2.3, *"),
+            (">1.2.3-beta.1", ">1.2.3-beta.1, *"),
+            (">1.2", ">=1.3.0, *"),
+            (">1", ">=2.0.0, *"),
+            (">=1.2.3", ">=1.2.3, *"),
+            (">=1.2.3-beta.1", ">=1.2.3-beta.1, *"),
+            (">=1.2", ">=1.2.0, *"),
+            (">=1", ">=1.0.0, *"),
+            (">=0.0.0", "*, *"),
+            ("<1.2.3", "*, <1.2.3"),
+            ("<1.2.3-beta.1", "*, <1.2.3-beta.1"),
+            ("<1.2", "*, <1.2.0"),
+            ("<1", "*, <1.0.0"),
+            ("<=1.2.3", "*, <=1.2.3"),
+            ("<=1.2", "*, <1.3.0"),
+            ("<=1", "*, <2.0.0"),
+            ("~1.2.3", ">=1.2.3, <1.3.0"),
+            ("~1.2.3-beta.1", ">=1.2.3-beta.1, <1.3.0"),
+            ("~1.2", ">=1.2.0, <1.3.0"),
+            ("~1", ">=1.0.0, <2.0.0"),
+            ("~0.2.3", ">=0.2.3, <0.3.0"),
+            ("~0", "*, <1.0.0"),
+            ("~>1.2", ">=1.2.0, <1.3.0"),
+            ("^1.2.3", ">=1.2.3, <2.0.0"),
+            ("^1.2.3-beta.1", ">=1.2.3-beta.1, <2.0.0"),
+            ("^1.2", ">=1.2.0, <2.0.0"),
+            ("^1", ">=1.0.0, <2.0.0"),
+            ("^0.2.3", ">=0.2.3, <0.3.0"),
+            ("^0.2", ">=0.2.0, <0.3.0"),
+            ("^0.0.3", ">=0.0.3, <0.0.4"),
+            ("^0.0", "*, <0.1.0"),
+            ("^0", "*, <1.0.0"),
+            ("1.2.3", ">=1.2.3, <2.0.0"),
+            ("0.0.3", ">=0.0.3, <0.0.4"),
+            ("1.2.*", ">=1.2.0, <1.3.0"),
+            ("1.*", ">=1.0.0, <2.0.0"),
+            ("1.x.3", ">=1.0.0, <2.0.0"),
+            ("0.*", "*, <1.0.0"),
+        ];
+
+        for (input, expected) in table.iter() {
+            let comparators = parse_comparators(input).unwrap();
+            assert_eq!(comparators.len(), 1, "{}", input);
+            assert_eq!(comparators[0].len(), 1, "{}", input);
+            assert_eq!(&expand(&comparators[0][0]).unwrap(), expected, "{}", input);
+        }
+
+        // Components which can't go any higher carry over into the next one.
+        // `semver-parser` itself overflows on most of these, so they can't be
+        // parsed from text.
+        let max = u64::MAX;
+        let comparator = |op, major, minor, patch| ReqComparator {
+            op,
+            major,
+            minor,
+            patch,
+            pre: vec![],
+        };
+        for (input, expected) in vec![
+            (
+                comparator(LessEq, 1, Some(max), None),
+                "*, <2.0.0".to_owned(),
+            ),
+            (
+                comparator(LessEq, 1, Some(2), Some(max)),
+                format!("*, <=1.2.{}", max),
+            ),
+            (comparator(LessEq, max, None, None), "*, *".to_owned()),
+            (
+                comparator(Greater, 1, Some(max), None),
+                ">=2.0.0, *".to_owned(),
+            ),
+            (
+                comparator(Exact, 1, Some(max), None),
+                format!(">=1.{}.0, <2.0.0", max),
+            ),
+            (
+                comparator(Tilde, 1, Some(max), None),
+                format!(">=1.{}.0, <2.0.0", max),
+            ),
+            (
+                comparator(Tilde, max, None, None),
+                format!(">={}.0.0, *", max),
+            ),
+            (
+                comparator(Caret, max, None, None),
+                format!(">={}.0.0, *", max),
+            ),
+            (
+                comparator(Caret, 0, Some(max), None),
+                format!(">=0.{}.0, <1.0.0", max),
+            ),
+            (
+                comparator(Caret, 0, Some(0), Some(max)),
+                format!(">=0.0.{}, <0.1.0", max),
+            ),
+            (
+                comparator(Wildcard, 1, Some(max), None),
+                format!(">=1.{}.0, <2.0.0", max),
+            ),
+        ] {
+            assert_eq!(expand(&input).unwrap(), expected, "{:?}", input);
+        }
+
+        // There is no version above the highest one
+        assert_eq!(
+            expand(&comparator(Greater, max, None, None)),
+            Err(RangeError::EmptyRange)
+        );
+    }
+
+    #[test]
+    fn authored_comparators() {
+        use super::ReqOp::*;
+
+        let comparator = |op, major, minor, patch| ReqComparator {
+            op,
+            major,
+            minor,
+            patch,
+            pre: vec![],
+        };
+
+        for (input, expected) in vec![
+            ("", vec![vec![]]),
+            ("*", vec![vec![]]),
+            (">= *", vec![vec![]]),
+            ("< *", vec![vec![comparator(Less, 0, Some(0), Some(0))]]),
+            (
+                ">= 1.2, < 2 || ~1",
+                vec![
+                    vec![
+                        comparator(GreaterEq, 1, Some(2), None),
+                        comparator(Less, 2, None, None),
+                    ],
+                    vec![comparator(Tilde, 1, None, None)],
+                ],
+            ),
+            (
+                "1.2 - 2",
+                vec![vec![
+                    comparator(GreaterEq, 1, Some(2), None),
+                    comparator(LessEq, 2, None, None),
+                ]],
+            ),
+            (
+                "1.2.3+build 1.x",
+                vec![vec![
+                    comparator(Caret, 1, Some(2), Some(3)),
+                    comparator(Wildcard, 1, None, None),
+                ]],
+            ),
+        ] {
+            assert_eq!(parse_comparators(input).unwrap(), expected, "{}", input);
+        }
+
+        let comparators = parse_comparators("~> 1.2.3-beta.1").unwrap();
+        assert_eq!(
+            comparators[0][0].pre,
+            vec![
+                Identifier::AlphaNumeric("beta".to_owned()),
+                Identifier::Numeric(1)
+            ]
+        );
+    }
+
+    #[test]
+    fn invalid_ranges_never_overlap() {
+        let invalid = UnaffectedRange {
+            start: Bound::Inclusive(Version::new(2, 0, 0)),
+            end: Bound::Exclusive(Version::new(1, 0, 0)),
+            origin: RangeOrigin::Patched,
+        };
+        assert!(!invalid.is_valid());
+
+        let everything = parse_range("*");
+        assert!(!invalid.overlaps(&everything));
+        assert!(!everything.overlaps(&invalid));
+        assert!(!invalid.overlaps(&invalid));
+        assert_eq!(invalid.intersection(&everything), None);
+    }
+
+    #[test]
+    fn subtract() {
+        let range = parse_range(">= 1.0.0, < 2.0.0");
+
+        // no overlap
+        assert_eq!(
+            range.subtract(&parse_range(">= 2.0.0")),
+            vec![range.clone()]
+        );
+        assert_eq!(range.subtract(&parse_range("< 1.0.0")), vec![range.clone()]);
+
+        // partial overlap on either side
+        assert_eq!(
+            range.subtract(&parse_range("<= 1.2.0")),
+            vec![parse_range("> 1.2.0, < 2.0.0")]
+        );
+        assert_eq!(
+            range.subtract(&parse_range("> 1.5.0, < 3.0.0")),
+            vec![parse_range(">= 1.0.0, <= 1.5.0")]
+        );
+
+        // full containment
+        assert_eq!(range.subtract(&range), vec![]);
+        assert_eq!(range.subtract(&parse_range("*")), vec![]);
+
+        // interior cut
+        assert_eq!(
+            range.subtract(&parse_range(">= 1.2.0, <= 1.5.0")),
+            vec![
+                parse_range(">= 1.0.0, < 1.2.0"),
+                parse_range("> 1.5.0, < 2.0.0")
+            ]
+        );
+        assert_eq!(
+            parse_range("*").subtract(&parse_range("= 1.2.0")),
+            vec![parse_range("< 1.2.0"), parse_range("> 1.2.0")]
+        );
+
+        // cutting at a shared start leaves only the part above
+        assert_eq!(
+            range.subtract(&parse_range(">= 1.0.0, < 1.5.0")),
+            vec![parse_range(">= 1.5.0, < 2.0.0")]
+        );
+        assert_eq!(
+            range.subtract(&parse_range("> 1.0.0, < 1.5.0")),
+            vec![parse_range("= 1.0.0"), parse_range(">= 1.5.0, < 2.0.0")]
+        );
+    }
+
+    #[test]
+    fn origin() {
+        let range = parse_range(">= 1.0.0");
+        assert_eq!(range.origin(), RangeOrigin::Patched);
+
+        let range = range.with_origin(RangeOrigin::Unaffected);
+        assert_eq!(range.origin(), RangeOrigin::Unaffected);
+        assert_ne!(range, parse_range(">= 1.0.0"));
+        assert_eq!(
+            range.subtract(&parse_range("= 1.5.0"))[0].origin(),
+            RangeOrigin::Unaffected
+        );
+    }
+
+    #[test]
+    fn authored_ranges() {
+        let ranges = AuthoredRange::parse("< 2.0 || >= 2.1, < 3 || ^3.1").unwrap();
+
+        let authored: Vec<_> = ranges.iter().map(AuthoredRange::authored).collect();
+        assert_eq!(authored, vec!["< 2.0", ">= 2.1, < 3", "^3.1"]);
+
+        assert_eq!(ranges[0].range(), &parse_range("< 2.0.0"));
+        assert_eq!(
+            ranges[0].range().end(),
+            &Bound::Exclusive(Version::new(2, 0, 0))
+        );
+        assert_eq!(ranges[1].range(), &parse_range(">= 2.1.0, < 3.0.0"));
+        assert_eq!(ranges[2].range(), &parse_range(">= 3.1.0, < 4.0.0"));
+
+        // Conversion agrees with going through `VersionReq`
+        let req = VersionReq::parse("< 2.0 || >= 2.1, < 3 || ^3.1").unwrap();
+        let normalized: Vec<_> = ranges.iter().map(|r| r.range().clone()).collect();
+        assert_eq!(normalized, ranges_from_version_req(&req).unwrap());
+    }
+
+    #[test]
+    fn authored_range_errors() {
+        assert!(matches!(
+            AuthoredRange::parse("< 2.0 || not a version"),
+            Err(RangeError::InvalidRequirement(_))
+        ));
+        assert_eq!(
+            AuthoredRange::parse(">= 1, >= 2"),
+            Err(RangeError::Requirement {
+                req: ">= 1, >= 2".to_owned(),
+                source: Box::new(RangeError::MultipleLowerBounds),
+            })
+        );
+    }
+
+    #[derive(Debug, Deserialize)]
+    struct Versions {
+        #[serde(deserialize_with = "deserialize_ranges")]
+        patched: Vec<UnaffectedRange>,
+    }
+
+    #[test]
+    fn deserialize_toml() {
+        let versions: Versions =
+            toml::from_str(r#"patched = [">= 1.2.3, < 1.3.0", ">= 1.4.0 || = 0.9.1"]"#).unwrap();
+
+        assert_eq!(
+            versions.patched,
+            vec![
+                parse_range(">= 1.2.3, < 1.3.0"),
+                parse_range(">= 1.4.0"),
+                parse_range("= 0.9.1"),
+            ]
+        );
+    }
+
+    #[test]
+    fn deserialize_toml_errors() {
+        let unparsable = toml::from_str::<Versions>(r#"patched = ["not a version"]"#)
+            .unwrap_err()
+            .to_string();
+        assert!(unparsable.contains("invalid version requirement \"not a version\""));
+        assert!(unparsable.contains("patched"));
+
+        let unconvertible = toml::from_str::<Versions>(r#"patched = [">= 1.0, >= 2.0"]"#)
+            .unwrap_err()
+            .to_string();
+        assert!(unconvertible.contains("multiple lower bounds in \">= 1.0, >= 2.0\""));
+        assert!(unconvertible.contains("patched"));
+    }
+
+    #[test]
+    fn from_str() {
+        assert_eq!(
+            ">= 1.0.0, < 2.0.0".parse::<UnaffectedRange>().unwrap(),
+            UnaffectedRange::new(
+                Bound::Inclusive(Version::new(1, 0, 0)),
+                Bound::Exclusive(Version::new(2, 0, 0))
+            )
+            .unwrap()
+        );
+        assert_eq!(
+            "< 1.5".parse::<UnaffectedRange>().unwrap(),
+            UnaffectedRange::new(Bound::Unbounded, Bound::Exclusive(Version::new(1, 5, 0)))
+                .unwrap()
+        );
+
+        // Display output parses back into the same range
+        for input in &["*", "=1.2.3", ">1.0.0, <=2.0.0", "<0.5.0-beta"] {
+            let range: UnaffectedRange = input.parse().unwrap();
+            assert_eq!(&range.to_string(), input);
+        }
+    }
+
+    #[test]
+    fn from_str_errors() {
+        assert!(matches!(
+            "not a version".parse::<UnaffectedRange>(),
+            Err(RangeError::InvalidRequirement(_))
+        ));
+        assert_eq!(
+            "< 1.0.0 || >= 2.0.0".parse::<UnaffectedRange>(),
+            Err(RangeError::MultipleRanges)
+        );
+        assert_eq!(
+            ">= 1.0.0, < 1.5.0, >= 2.0.0".parse::<UnaffectedRange>(),
+            Err(RangeError::TooManyPredicates)
+        );
+    }
+
+    /// A comparator in any of the forms `semver` accepts, with partial and
+    /// pre-release versions.
+    ///
+    /// `u64::MAX` only appears as the patch of a full version, which no
+    /// operator needs to increment. Partial versions ending in `u64::MAX`
+    /// are left out, since `semver-parser` itself overflows when expanding
+    /// e.g. `<= 18446744073709551615` into `< 18446744073709551616.0.0`.
+    fn arb_comparator() -> impl Strategy<Value = String> {
+        let op = prop::sample::select(vec!["", "=", ">", ">=", "<", "<=", "~", "^"]);
+        let version = prop::sample::select(vec![
+            "0",
+            "1",
+            "0.0",
+            "1.2",
+            "0.0.0",
+            "0.0.3",
+            "1.2.3",
+            "1.2.3-0",
+            "1.2.3-beta.1",
+            "*",
+            "1.*",
+            "1.2.*",
+            "1.2.18446744073709551615",
+        ]);
+        (op, version).prop_map(|(op, version)| format!("{}{}", op, version))
+    }
+
+    /// A requirement of several `||`-separated ranges of comparators
+    fn arb_requirement_string() -> impl Strategy<Value = String> {
+        let range = prop::collection::vec(arb_comparator(), 1..5).prop_map(|c| c.join(", "));
+        prop::collection::vec(range, 1..4).prop_map(|r| r.join(" || "))
+    }
+
+    /// A `semver-parser` range of arbitrary primitive comparators, including
+    /// ones `semver` would never produce
+    fn arb_semver_range() -> impl Strategy<Value = Range> {
+        let op = prop::sample::select(vec![Op::Lt, Op::Lte, Op::Gt, Op::Gte, Op::Eq]);
+        let comparator = (op, 0..3u64, 0..3u64, 0..3u64, any::<bool>()).prop_map(
+            |(op, major, minor, patch, pre)| Comparator {
+                op,
+                major,
+                minor,
+                patch,
+                pre: if pre {
+                    vec![semver_parser::Identifier::AlphaNumeric("alpha".to_owned())]
+                } else {
+                    vec![]
+                },
+            },
+        );
+        prop::collection::vec(comparator, 0..6).prop_map(|comparator_set| Range {
+            comparator_set,
+            compat: Compat::Cargo,
+        })
+    }
+
+    proptest! {
+        // Requirements come from untrusted advisories, so converting them
+        // must report errors rather than panic
+        #[test]
+        fn conversion_never_panics(input in prop_oneof![arb_requirement_string(), "\\PC{0,24}"]) {
+            let _ = parse_req_structure(&input);
+            let _ = AuthoredRange::parse(&input);
+            let _ = input.parse::<UnaffectedRange>();
+
+            if let Ok(req) = VersionReq::parse(&input) {
+                let _ = UnaffectedRange::from_version_req(&req);
+                let _ = affected_ranges(&[req]);
+            }
+        }
+
+        #[test]
+        fn range_conversion_never_panics(range in arb_semver_range()) {
+            let _ = UnaffectedRange::try_from(range.clone());
+
+            let comparators: Vec<_> = range
+                .comparator_set
+                .iter()
+                .map(ReqComparator::from)
+                .collect();
+            let _ = single_range(&comparators);
+        }
+    }
+}